@@ -1,24 +1,77 @@
 use crate::{
-    combat::GaurdIconMarker,
+    bout::{Bout, BoutEvent, FighterInput, BEAT_RANGE},
+    combat::{next_screen, step_bout, BoutInputs, GaurdIconMarker},
     distance,
     fighter::*,
-    player::{P2Timer, PlayerMarker},
-    state::{GameState, Screen},
+    rng::Rng,
+    state::{local_bout, GameMode, Screen},
 };
-use bevy::prelude::*;
+use bevy::{app::AppExit, prelude::*};
+use q_learning::{q_table_path, QAgent, QMode};
+use std::{ops::Not, sync::Mutex, thread};
+
+pub mod q_learning;
+
+/// how many bout ticks the computer players wait between decisions (a quarter second).
+pub const THINK_TICKS: u64 = 16;
 
 #[derive(Component)]
 pub struct Player2Marker;
 
+/// which computer player controls player two.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ComputerAi {
+    /// the scripted, always attacking, computer player.
+    Offensive,
+    /// the q-learning computer player. it plays what it learned in training, and only keeps
+    /// learning while you fence it if learning is turned on.
+    QLearning,
+}
+
+impl Not for ComputerAi {
+    type Output = ComputerAi;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Offensive => Self::QLearning,
+            Self::QLearning => Self::Offensive,
+        }
+    }
+}
+
 pub struct AiPlugin;
 
 impl Plugin for AiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(Screen::NewBout), spawn_fighter_two)
-            .add_systems(OnExit(Screen::NewBout), setup_p2_timer)
-            .add_systems(OnExit(Screen::NewBout), spawn_p2_gaurd_icon)
-            .add_systems(Update, fighter_2_movement.run_if(in_state(Screen::Game)))
-            .add_systems(Update, reset_fighter.run_if(in_state(Screen::Game)));
+        app.insert_resource(ComputerAi::Offensive)
+            .add_systems(Startup, load_q_agent)
+            .add_systems(Last, save_q_agent_on_exit)
+            .add_systems(OnEnter(Screen::NewBout), spawn_fighter_two)
+            .add_systems(OnExit(Screen::NewBout), reset_q_agent)
+            .add_systems(OnEnter(Screen::NewBout), spawn_p2_gaurd_icon)
+            .add_systems(
+                OnEnter(Screen::Replay),
+                (spawn_fighter_two, spawn_p2_gaurd_icon),
+            )
+            .add_systems(
+                OnEnter(Screen::HowToPlay),
+                (spawn_fighter_two, spawn_p2_gaurd_icon),
+            )
+            .add_systems(
+                FixedUpdate,
+                computer_movement
+                    .before(step_bout)
+                    .run_if(in_state(Screen::Game))
+                    .run_if(local_bout),
+            )
+            .add_systems(
+                FixedUpdate,
+                q_agent_observe
+                    .after(step_bout)
+                    .run_if(in_state(Screen::Game))
+                    .run_if(resource_equals(GameMode::VsComputer))
+                    .run_if(resource_equals(ComputerAi::QLearning)),
+            );
     }
 }
 
@@ -28,21 +81,10 @@ fn spawn_fighter_two(
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
     let texture = asset_server.load("sprites/fighter-sprites.png");
-    let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 2, 1, None, None);
+    let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 8, 1, None, None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
 
     commands.spawn((
-        Fighter {
-            gaurd: Gaurd::Left,
-            position: 2.0,
-            stance: Stance::Offence,
-            handed: Handed::Right,
-            player: Player::Two,
-            // contoller: Controller::Computer,
-            parrying: false,
-            action: Action::from(Move::EnGarde),
-            crouched: false,
-        },
         SpriteSheetBundle {
             texture,
             atlas: TextureAtlas {
@@ -88,81 +130,172 @@ fn spawn_p2_gaurd_icon(
     ));
 }
 
-fn setup_p2_timer(mut commands: Commands, time: Res<Time>) {
-    commands.insert_resource(P2Timer(time.clone()));
-}
-
-pub fn fighter_2_movement(
-    mut fighter_2_query: Query<(&mut Fighter, &mut TextureAtlas), Without<PlayerMarker>>,
-    player1_query: Query<&Fighter, With<PlayerMarker>>,
-    mut p2_timer: ResMut<P2Timer>,
-    time: Res<Time>,
-    mut world_state: ResMut<GameState>,
+/// decides the next input of every fighter controlled by the computer. it thinks every
+/// `THINK_TICKS` bout ticks, so it waits out a pause along with the bout.
+pub fn computer_movement(
+    bout: Res<Bout>,
+    mut inputs: ResMut<BoutInputs>,
+    computer_ai: Res<ComputerAi>,
+    mut q_agent: ResMut<QAgent>,
 ) {
-    if let (Ok((mut fighter, mut atlas)), Ok(player1)) =
-        (fighter_2_query.get_single_mut(), player1_query.get_single())
-    {
-        if !fighter.action.blocked() && p2_timer.0.elapsed_seconds() >= 0.25 {
-            // do computer player action
-            let distance = distance(player1.position, fighter.position);
-            let prev_gaurd = fighter.gaurd.clone();
-
-            if distance >= 4.0 {
-                // advance if player too far,
-                fighter.set_action(Move::Advance);
-                world_state.row = Some(Player::Two);
-            } else if distance <= 3.25 && world_state.row == Some(Player::One) {
-                // retreat if player too close,
-                fighter.set_action(Move::Retreat);
-
-                if world_state.row == Some(Player::Two) {
-                    world_state.row = None;
-                }
-            } else if distance <= 1.0 && world_state.row == Some(Player::Two) {
-                // lunge if in range
-                fighter.set_action(Move::Lunge);
-                atlas.index += 1;
-                world_state.lunger = Some(Player::Two);
-            } else if player1.lunged() && world_state.row == Some(Player::One) && distance <= 1.75 {
-                // parry if enemy lunges and has right of way
-                // TODO: make fail (but only sometimes)
-                // TODO: update once parying gets more advanced.
-                fighter.gaurd = player1.gaurd;
-
-                world_state.row = Some(Player::Two);
-            } else if world_state.row == Some(Player::Two)
-                || world_state.row.is_none()
-                || player1.action.act == Move::EnGarde
-            {
-                fighter.set_action(Move::Advance);
-            }
+    let thinking = bout.tick.is_multiple_of(THINK_TICKS);
 
-            if prev_gaurd != fighter.gaurd
-                && player1.lunged()
-                && fighter.gaurd.parries(player1.gaurd)
-            {
-                fighter.parrying = true;
-            } else if !(player1.lunged() && fighter.gaurd.parries(player1.gaurd)) {
-                fighter.parrying = false;
+    for player in bout.players(Controller::Computer) {
+        *inputs.get_mut(player) = if bout.can_act(player) && thinking {
+            match *computer_ai {
+                ComputerAi::Offensive => offensive(&bout, player),
+                ComputerAi::QLearning => q_agent.act(&bout, player),
             }
+        } else {
+            inputs.get(player).held()
+        };
+    }
+}
+
+/// the scripted, offensive, computer player. decides what the fighter controlled by `player`
+/// should do next.
+pub fn offensive(bout: &Bout, player: Player) -> FighterInput {
+    let fighter = bout.fighter(player);
+    let opponent = bout.fighter(player.other());
+    let row = bout.state.row;
+    let distance = distance(opponent.position, fighter.position);
+    let mut input = FighterInput::default();
+
+    if bout
+        .state
+        .riposte
+        .is_some_and(|riposte| riposte.player == player && !riposte.thrown)
+    {
+        // hit back straight after a parry,
+        input.action = Some(Move::Riposte);
+    } else if opponent.action.act == Move::Riposte {
+        // parry a riposte coming back at us,
+        input.gaurd = Some(fighter.gaurd_against(opponent));
+    } else if distance >= 5.0 {
+        // close a big gap quickly,
+        input.action = Some(Move::DoubleAdvance);
+    } else if distance >= 4.0 {
+        // advance if player too far,
+        input.action = Some(Move::Advance);
+    } else if opponent.attacking()
+        && row == Some(player.other())
+        && opponent.line() != Gaurd::Down
+        && distance <= 1.75
+    {
+        // duck under a lunge that is not coming in low.
+        input.crouch = true;
+    } else if distance <= BEAT_RANGE && row == Some(player.other()) && !opponent.attacking() {
+        // beat the blade to take right of way back, once the gaurds match.
+        if fighter.parries(opponent) {
+            input.action = Some(Move::Beat);
+        } else {
+            input.gaurd = Some(fighter.gaurd_against(opponent));
         }
+    } else if distance <= 2.0 && row == Some(player.other()) && !opponent.attacking() {
+        // get out of reach quickly if the player is right on top of us,
+        input.action = Some(Move::DoubleRetreat);
+    } else if distance <= 3.25 && row == Some(player.other()) {
+        // retreat if player too close,
+        input.action = Some(Move::Retreat);
+    } else if distance <= 1.7 && row == Some(player) && !fighter.reaches(opponent) {
+        // a crouched opponent can only be hit low.
+        input.gaurd = Some(Gaurd::Down);
+    } else if distance <= 1.0 && row == Some(player) {
+        // lunge if in range
+        input.action = Some(Move::Lunge);
+    } else if distance <= 1.4 && row == Some(player) && opponent.action.act == Move::EnGarde {
+        // jump in on an opponent standing still,
+        input.action = Some(Move::Balestra);
+    } else if distance <= 1.7
+        && row == Some(player)
+        && matches!(opponent.action.act, Move::Retreat | Move::DoubleRetreat)
+    {
+        // run down an opponent backing away,
+        input.action = Some(Move::Fleche);
+    } else if opponent.attacking() && row == Some(player.other()) && distance <= 1.75 {
+        // parry if enemy lunges and has right of way
+        // TODO: make fail (but only sometimes)
+        // TODO: update once parying gets more advanced.
+        input.gaurd = Some(fighter.gaurd_against(opponent));
+    } else if row == Some(player) || row.is_none() || opponent.action.act == Move::EnGarde {
+        input.action = Some(Move::Advance);
+    }
 
-        let pos_d = fighter.update_movement(time.clone());
+    // hang back in defence while the opponent has right of way and is close enough to attack,
+    // go on the offence otherwise.
+    input.stance = Some(if row == Some(player.other()) && distance <= 3.25 {
+        Stance::Defence
+    } else {
+        Stance::Offence
+    });
 
-        fighter.position -= pos_d;
+    input
+}
 
-        if p2_timer.0.elapsed_seconds() >= 0.25 {
-            *p2_timer = P2Timer(time.clone());
-        }
+/// hands the q-learning player the reward for what happened during the last tick.
+fn q_agent_observe(
+    bout: Res<Bout>,
+    mut q_agent: ResMut<QAgent>,
+    mut bout_events: EventReader<BoutEvent>,
+) {
+    let events: Vec<BoutEvent> = bout_events.read().copied().collect();
+
+    // the q-learning player only learns from one fighter at a time.
+    let Some(player) = bout.players(Controller::Computer).first().copied() else {
+        return;
+    };
+
+    q_agent.observe(&bout, player, &events);
+
+    // nothing is learned outside of training, so there is nothing new to save.
+    if q_agent.mode == QMode::Training && events.iter().any(|event| next_screen(*event).is_some()) {
+        save_q_agent_in_background(q_agent.clone());
     }
 }
 
-fn reset_fighter(mut player_query: Query<(&Fighter, &mut TextureAtlas), Without<PlayerMarker>>) {
-    if let Ok((player, mut atlas)) = player_query.get_single_mut()
-        && player.action.act != Move::Lunge
-        && atlas.index > 0
-    {
-        debug!("resetting player sprite to standing position");
-        atlas.index = 0;
+/// the computer player plays what it learned in training, learning is turned on from the mode
+/// select screen. with nothing learned yet it starts out learning, as it would only stand there
+/// otherwise.
+fn load_q_agent(mut commands: Commands) {
+    let mut q_agent = QAgent::load_or_new(&q_table_path(), QMode::Inference, Rng::from_time());
+
+    if q_agent.untrained() {
+        info!("the q-table is untrained, the q-learning computer player will learn as it fences");
+        q_agent.mode = QMode::Training;
     }
+
+    commands.insert_resource(q_agent);
+}
+
+fn save_q_agent_on_exit(exit: EventReader<AppExit>, q_agent: Res<QAgent>) {
+    if !exit.is_empty() {
+        save_q_agent(&q_agent);
+    }
+}
+
+/// held while the q-table is being written, so saves made one after another don't write over
+/// each other.
+static SAVING: Mutex<()> = Mutex::new(());
+
+/// saves the q-learning players brain on another thread, the q-table is too big to write
+/// between frames without a hitch.
+fn save_q_agent_in_background(q_agent: QAgent) {
+    thread::spawn(move || save_q_agent(&q_agent));
+}
+
+/// saves the q-learning players brain, a failed save is logged and otherwise ignored. waits for
+/// a save already being written to finish first.
+pub fn save_q_agent(q_agent: &QAgent) {
+    let _saving = SAVING.lock().unwrap_or_else(|e| e.into_inner());
+    let path = q_table_path();
+
+    match q_agent.save(&path) {
+        Ok(()) => debug!("saved q-table to {}", path.display()),
+        Err(e) => error!("could not save q-table to {}: {e}", path.display()),
+    }
+}
+
+/// makes sure a bout that was quit part way is not learned from in the next one.
+fn reset_q_agent(mut q_agent: ResMut<QAgent>) {
+    q_agent.abandon_episode();
 }
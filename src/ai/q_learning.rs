@@ -0,0 +1,507 @@
+use crate::{
+    bout::{Bout, BoutEvent, FighterInput},
+    distance,
+    fighter::*,
+    paths::data_dir,
+    rng::Rng,
+};
+use bevy::prelude::*;
+use std::{
+    fs,
+    io::{self, ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+/// identifies a saved q-table file.
+const MAGIC: &[u8; 4] = b"FGQT";
+/// version of the save file layout.
+const FILE_VERSION: u32 = 1;
+/// bump whenever the meaning of a state or action index changes, so old q-tables get thrown
+/// away instead of being misread.
+const ENCODING_VERSION: u32 = 7;
+
+/// distance to the opponent is bucketed into quarter meters, anything past 4 meters shares the
+/// last bucket.
+const DISTANCE_BUCKETS: usize = 17;
+const DISTANCE_STEP: f32 = 0.25;
+const GAURDS: usize = 4;
+/// nobody, the agent, or the opponent (used for both right of way and the lunger).
+const SIDES: usize = 3;
+/// double steps look the same as single ones, and every attack looks like a lunge.
+const MOVES: usize = 5;
+/// standing or crouched.
+const POSTURES: usize = 2;
+/// how many distinct states the agent can observe.
+pub const STATES: usize =
+    DISTANCE_BUCKETS * GAURDS * GAURDS * SIDES * SIDES * MOVES * MOVES * POSTURES * POSTURES;
+
+/// everything the agent is able to do on its turn.
+pub const ACTIONS: [QAction; 15] = [
+    QAction::Move(Move::EnGarde),
+    QAction::Move(Move::Advance),
+    QAction::Move(Move::Retreat),
+    QAction::Move(Move::Lunge),
+    QAction::Move(Move::Fleche),
+    QAction::Move(Move::Balestra),
+    QAction::Move(Move::Riposte),
+    QAction::Move(Move::Beat),
+    QAction::Move(Move::DoubleAdvance),
+    QAction::Move(Move::DoubleRetreat),
+    QAction::Gaurd(Gaurd::Right),
+    QAction::Gaurd(Gaurd::Down),
+    QAction::Gaurd(Gaurd::Left),
+    QAction::Gaurd(Gaurd::Up),
+    QAction::Crouch,
+];
+
+/// reward for scoring a touch (negated when the opponent scores).
+const TOUCH_REWARD: f32 = 1.0;
+/// reward for parrying the opponent and stealing right of way (negated when parried).
+const PARRY_REWARD: f32 = 0.25;
+/// reward for beating the opponents blade and stealing right of way (negated when beaten).
+const BEAT_REWARD: f32 = 0.15;
+/// punishment for backing off the end of the piste.
+const OUT_OF_BOUNDS_REWARD: f32 = -0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QAction {
+    Move(Move),
+    Gaurd(Gaurd),
+    /// crouch untill the next action.
+    Crouch,
+}
+
+impl From<QAction> for FighterInput {
+    fn from(value: QAction) -> Self {
+        match value {
+            QAction::Move(act) => FighterInput {
+                action: Some(act),
+                ..default()
+            },
+            QAction::Gaurd(gaurd) => FighterInput {
+                gaurd: Some(gaurd),
+                ..default()
+            },
+            QAction::Crouch => FighterInput {
+                crouch: true,
+                ..default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QMode {
+    /// explores with epsilon-greedy action selection and updates the q-table as it plays.
+    Training,
+    /// always takes the best known action and never updates the q-table.
+    Inference,
+}
+
+/// a tabular q-learning computer player.
+#[derive(Resource, Debug, Clone)]
+pub struct QAgent {
+    /// the expected reward of each action, for each state.
+    pub table: Vec<[f32; ACTIONS.len()]>,
+    /// learning rate.
+    pub alpha: f32,
+    /// discount factor for future rewards.
+    pub gamma: f32,
+    /// chance of taking a random action while training.
+    pub epsilon: f32,
+    pub mode: QMode,
+    rng: Rng,
+    /// the state the last action was taken from, and the index of that action.
+    last: Option<(usize, usize)>,
+    /// reward collected since the last action was taken.
+    pending_reward: f32,
+}
+
+impl QAgent {
+    /// a clueless agent that has not learned anything yet.
+    pub fn new(mode: QMode, rng: Rng) -> Self {
+        Self {
+            table: vec![[0.0; ACTIONS.len()]; STATES],
+            alpha: 0.1,
+            gamma: 0.95,
+            epsilon: 0.1,
+            mode,
+            rng,
+            last: None,
+            pending_reward: 0.0,
+        }
+    }
+
+    /// loads a saved agent, or starts a fresh one if there is no save (or it can't be used).
+    pub fn load_or_new(path: &Path, mode: QMode, rng: Rng) -> Self {
+        match Self::load(path, mode, rng.clone()) {
+            Ok(agent) => {
+                info!("loaded q-table from {}", path.display());
+                agent
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                info!("no q-table found at {}, starting fresh", path.display());
+                Self::new(mode, rng)
+            }
+            Err(e) => {
+                warn!(
+                    "could not load q-table from {}: {e}. starting fresh",
+                    path.display()
+                );
+                Self::new(mode, rng)
+            }
+        }
+    }
+
+    pub fn load(path: &Path, mode: QMode, rng: Rng) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let mut reader = bytes.as_slice();
+        let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_string());
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid("not a q-table file"));
+        }
+
+        if read_u32(&mut reader)? != FILE_VERSION {
+            return Err(invalid("unsupported file version"));
+        }
+
+        if read_u32(&mut reader)? != ENCODING_VERSION
+            || read_u32(&mut reader)? as usize != STATES
+            || read_u32(&mut reader)? as usize != ACTIONS.len()
+        {
+            return Err(invalid("q-table was made for a different state encoding"));
+        }
+
+        let mut agent = Self::new(mode, rng);
+        agent.alpha = read_f32(&mut reader)?;
+        agent.gamma = read_f32(&mut reader)?;
+        agent.epsilon = read_f32(&mut reader)?;
+
+        for values in agent.table.iter_mut() {
+            for value in values.iter_mut() {
+                *value = read_f32(&mut reader)?;
+            }
+        }
+
+        if !reader.is_empty() {
+            return Err(invalid("trailing data after q-table"));
+        }
+
+        Ok(agent)
+    }
+
+    /// writes the q-table and hyperparameters to `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut bytes = Vec::with_capacity(32 + STATES * ACTIONS.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+
+        for n in [
+            FILE_VERSION,
+            ENCODING_VERSION,
+            STATES as u32,
+            ACTIONS.len() as u32,
+        ] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+
+        for n in [self.alpha, self.gamma, self.epsilon] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+
+        for value in self.table.iter().flatten() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // write to the side and swap it in, so a crash mid write can't corrupt the old save.
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(tmp, path)
+    }
+
+    /// wether the agent has learned nothing at all. an untrained agent only ever stands en
+    /// garde, as every action ties.
+    pub fn untrained(&self) -> bool {
+        self.table.iter().flatten().all(|value| *value == 0.0)
+    }
+
+    /// forgets everything the agent has learned.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.mode, self.rng.clone());
+    }
+
+    /// picks the next thing for the fighter controlled by `player` to do. while training, this
+    /// also learns from the outcome of the previous action.
+    pub fn act(&mut self, bout: &Bout, player: Player) -> FighterInput {
+        let state = encode_state(bout, player);
+
+        if let Some((last_state, last_action)) = self.last {
+            let future = self.gamma * max(&self.table[state]);
+            self.learn(last_state, last_action, future);
+        }
+
+        let action = if self.mode == QMode::Training && self.rng.next_f32() < self.epsilon {
+            self.rng.below(ACTIONS.len())
+        } else {
+            best(&self.table[state])
+        };
+
+        self.last = Some((state, action));
+        ACTIONS[action].into()
+    }
+
+    /// collects the reward for what happened during a tick. ends the episode if the bout is over.
+    pub fn observe(&mut self, bout: &Bout, player: Player, events: &[BoutEvent]) {
+        self.pending_reward += reward(player, events);
+
+        if bout.over {
+            self.end_episode();
+        }
+    }
+
+    /// learns from the final action of a bout (nothing comes after it) and forgets it.
+    pub fn end_episode(&mut self) {
+        if let Some((last_state, last_action)) = self.last {
+            self.learn(last_state, last_action, 0.0);
+        }
+
+        self.abandon_episode();
+    }
+
+    /// forgets the current bout without learning from it (used when a bout is quit part way).
+    pub fn abandon_episode(&mut self) {
+        self.last = None;
+        self.pending_reward = 0.0;
+    }
+
+    fn learn(&mut self, state: usize, action: usize, future: f32) {
+        if self.mode == QMode::Training {
+            let q = &mut self.table[state][action];
+            *q += self.alpha * (self.pending_reward + future - *q);
+        }
+
+        self.pending_reward = 0.0;
+    }
+}
+
+/// where the computer players brain is kept between games.
+pub fn q_table_path() -> PathBuf {
+    data_dir().join("q-table.bin")
+}
+
+fn read_u32(reader: &mut &[u8]) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_f32(reader: &mut &[u8]) -> io::Result<f32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+
+    Ok(f32::from_le_bytes(buf))
+}
+
+/// the reward `player` earned from the events of a single tick.
+pub fn reward(player: Player, events: &[BoutEvent]) -> f32 {
+    events
+        .iter()
+        .map(|event| match *event {
+            BoutEvent::Touch(scorer, _) if scorer == player => TOUCH_REWARD,
+            BoutEvent::Touch(_, _) => -TOUCH_REWARD,
+            BoutEvent::Parry(parrier) if parrier == player => PARRY_REWARD,
+            BoutEvent::Parry(_) => -PARRY_REWARD,
+            BoutEvent::Beat(beater) if beater == player => BEAT_REWARD,
+            BoutEvent::Beat(_) => -BEAT_REWARD,
+            BoutEvent::OutOfBounds(p) if p == player => OUT_OF_BOUNDS_REWARD,
+            BoutEvent::OutOfBounds(_)
+            | BoutEvent::SideFlip
+            | BoutEvent::Simultaneous
+            | BoutEvent::TimeUp(_) => 0.0,
+        })
+        .sum()
+}
+
+/// turns what the fighter controlled by `player` can see into an index into the q-table.
+pub fn encode_state(bout: &Bout, player: Player) -> usize {
+    let fighter = bout.fighter(player);
+    let opponent = bout.fighter(player.other());
+    let distance = distance(fighter.position, opponent.position);
+
+    let side = |holder: Option<Player>| match holder {
+        None => 0,
+        Some(p) if p == player => 1,
+        Some(_) => 2,
+    };
+
+    let features = [
+        (
+            ((distance / DISTANCE_STEP) as usize).min(DISTANCE_BUCKETS - 1),
+            DISTANCE_BUCKETS,
+        ),
+        (fighter.gaurd.into(), GAURDS),
+        // the opponents gaurd as the agents own gaurd that would meet it, so the same table
+        // works whichever hand either of them fences with.
+        (
+            opponent
+                .gaurd
+                .for_hand(opponent.handed)
+                .for_hand(fighter.handed)
+                .into(),
+            GAURDS,
+        ),
+        (side(bout.state.row), SIDES),
+        (side(bout.state.lunger), SIDES),
+        (move_index(fighter.action.act), MOVES),
+        (move_index(opponent.action.act), MOVES),
+        (fighter.crouched.into(), POSTURES),
+        (opponent.crouched.into(), POSTURES),
+    ];
+
+    features
+        .into_iter()
+        .fold(0, |index, (feature, size)| index * size + feature)
+}
+
+fn move_index(act: Move) -> usize {
+    match act {
+        Move::EnGarde => 0,
+        Move::Advance | Move::DoubleAdvance => 1,
+        Move::Retreat | Move::DoubleRetreat => 2,
+        Move::Lunge | Move::Fleche | Move::Balestra | Move::Riposte => 3,
+        Move::Beat => 4,
+    }
+}
+
+fn max(values: &[f32; ACTIONS.len()]) -> f32 {
+    values.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// index of the highest valued action, ties go to the first.
+fn best(values: &[f32; ACTIONS.len()]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |(best_i, best_v), (i, v)| {
+            if *v > best_v {
+                (i, *v)
+            } else {
+                (best_i, best_v)
+            }
+        })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a file in the temp directory, removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(
+                std::env::temp_dir()
+                    .join(format!("fencing-game-{}-{name}.bin", std::process::id())),
+            )
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// an agent that has learned something, so a fresh one can be told apart from it.
+    fn trained() -> QAgent {
+        let mut agent = QAgent::new(QMode::Training, Rng::new(1));
+        agent.epsilon = 0.3;
+        agent.table[0][3] = 1.5;
+        agent.table[STATES - 1][ACTIONS.len() - 1] = -0.25;
+        agent
+    }
+
+    /// saves a trained agent, then lets `corrupt` change the file.
+    fn saved(name: &str, corrupt: impl FnOnce(&mut Vec<u8>)) -> TempFile {
+        let file = TempFile::new(name);
+        trained().save(&file.0).unwrap();
+
+        let mut bytes = fs::read(&file.0).unwrap();
+        corrupt(&mut bytes);
+        fs::write(&file.0, bytes).unwrap();
+
+        file
+    }
+
+    fn assert_invalid(file: &TempFile) {
+        let err = QAgent::load(&file.0, QMode::Inference, Rng::new(1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // the game starts a fresh agent instead.
+        let agent = QAgent::load_or_new(&file.0, QMode::Inference, Rng::new(1));
+        assert!(agent.table.iter().flatten().all(|value| *value == 0.0));
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let file = saved("round-trip", |_| {});
+        let agent = QAgent::load(&file.0, QMode::Inference, Rng::new(1)).unwrap();
+        let original = trained();
+
+        assert_eq!(agent.table, original.table);
+        assert_eq!(agent.epsilon, original.epsilon);
+        assert_eq!(agent.mode, QMode::Inference);
+    }
+
+    #[test]
+    fn truncated_file_is_rejected() {
+        let file = saved("truncated", |bytes| bytes.truncate(bytes.len() - 2));
+        let err = QAgent::load(&file.0, QMode::Inference, Rng::new(1)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let agent = QAgent::load_or_new(&file.0, QMode::Inference, Rng::new(1));
+        assert!(agent.table.iter().flatten().all(|value| *value == 0.0));
+    }
+
+    #[test]
+    fn other_encoding_version_is_rejected() {
+        // the encoding version follows the magic and the file version.
+        let file = saved("encoding", |bytes| {
+            bytes[8..12].copy_from_slice(&(ENCODING_VERSION + 1).to_le_bytes())
+        });
+        assert_invalid(&file);
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        let file = saved("trailing", |bytes| bytes.push(0));
+        assert_invalid(&file);
+    }
+
+    #[test]
+    fn missing_file_starts_fresh() {
+        let file = TempFile::new("missing");
+        let agent = QAgent::load_or_new(&file.0, QMode::Training, Rng::new(1));
+
+        assert!(agent.untrained());
+    }
+
+    #[test]
+    fn reset_forgets_training() {
+        let mut agent = trained();
+        assert!(!agent.untrained());
+
+        agent.reset();
+        assert!(agent.untrained());
+    }
+}
@@ -0,0 +1,364 @@
+//! fences two rollback peers against each other, in one process, over a pretend network that
+//! holds back, reorders, and drops packets. checks that both peers finish every bout exactly as
+//! a single machine with every input would have.
+//!
+//! ```sh
+//! cargo run --release --bin netsim -- --latency 6 --jitter 3 --loss 10 --input-delay 2
+//! ```
+use fencing_game::{
+    ai::{offensive, q_learning::ACTIONS, THINK_TICKS},
+    bout::{Bout, BoutEvent, FighterInput, TICK},
+    combat::next_screen,
+    fighter::Player,
+    lan::{
+        protocol::Message,
+        rollback::{Rollback, DEFAULT_INPUT_DELAY},
+    },
+    rng::Rng,
+};
+use std::{collections::BTreeMap, env, process::exit};
+
+/// bouts that drag on longer than this (in seconds) are called off.
+const MAX_BOUT_SECONDS: f32 = 60.0;
+/// how often the fencers do something other than what the scripted computer player would.
+const RANDOM_INPUT_CHANCE: f32 = 0.3;
+
+const USAGE: &str =
+    "usage: netsim [--bouts N] [--latency TICKS] [--jitter TICKS] [--loss PERCENT] \
+[--input-delay TICKS] [--seed N]";
+
+struct Args {
+    bouts: u64,
+    latency: u64,
+    jitter: u64,
+    /// the chance (from 0 to 1) that a packet is lost.
+    loss: f32,
+    input_delay: u64,
+    seed: u64,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Self {
+            bouts: 100,
+            latency: 4,
+            jitter: 2,
+            loss: 0.05,
+            input_delay: DEFAULT_INPUT_DELAY,
+            seed: Rng::from_time().next_u64(),
+        };
+        let mut argv = env::args().skip(1);
+
+        while let Some(arg) = argv.next() {
+            let mut value = || argv.next().ok_or(format!("{arg} needs a value"));
+
+            match arg.as_str() {
+                "--bouts" => args.bouts = parse(&value()?)?,
+                "--latency" => args.latency = parse(&value()?)?,
+                "--jitter" => args.jitter = parse(&value()?)?,
+                "--loss" => args.loss = parse::<f32>(&value()?)?.clamp(0.0, 100.0) / 100.0,
+                "--input-delay" => args.input_delay = parse(&value()?)?,
+                "--seed" => args.seed = parse(&value()?)?,
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    exit(0);
+                }
+                other => return Err(format!("unknown argument: {other}")),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("could not parse \"{value}\""))
+}
+
+/// one direction of the pretend network.
+struct Link {
+    /// packets on their way, and the tick they arrive on.
+    in_flight: Vec<(u64, Vec<u8>)>,
+    latency: u64,
+    jitter: u64,
+    loss: f32,
+    rng: Rng,
+}
+
+impl Link {
+    fn new(args: &Args, rng: Rng) -> Self {
+        Self {
+            in_flight: Vec::new(),
+            latency: args.latency,
+            jitter: args.jitter,
+            loss: args.loss,
+            rng,
+        }
+    }
+
+    fn send(&mut self, now: u64, message: &Message) {
+        if self.rng.next_f32() < self.loss {
+            return;
+        }
+
+        let jitter = self.rng.below(self.jitter as usize + 1) as u64;
+        self.in_flight
+            .push((now + self.latency + jitter, message.encode()));
+    }
+
+    /// the packets that have arrived by `now`, in the order they arrived.
+    fn deliver(&mut self, now: u64) -> Vec<Message> {
+        self.in_flight.sort_by_key(|(arrives, _)| *arrives);
+
+        let arrived = self
+            .in_flight
+            .iter()
+            .take_while(|(arrives, _)| *arrives <= now)
+            .count();
+
+        self.in_flight
+            .drain(..arrived)
+            .filter_map(|(_, bytes)| Message::decode(&bytes))
+            .collect()
+    }
+}
+
+/// one of the two machines.
+struct Peer {
+    player: Player,
+    bout: Bout,
+    rollback: Rollback,
+    /// the local input used on every tick.
+    inputs: BTreeMap<u64, FighterInput>,
+    /// how the bout ended, once that is final.
+    ended: Option<BoutEvent>,
+    rng: Rng,
+}
+
+impl Peer {
+    fn new(player: Player, input_delay: u64, rng: Rng) -> Self {
+        Self {
+            player,
+            bout: Bout::new(),
+            rollback: Rollback::new(player, input_delay),
+            inputs: (1..=input_delay)
+                .map(|frame| (frame, FighterInput::default()))
+                .collect(),
+            ended: None,
+            rng,
+        }
+    }
+
+    /// starts the next bout, keeps the score.
+    fn next_bout(&mut self, input_delay: u64) {
+        self.bout.reset();
+        self.rollback = Rollback::new(self.player, input_delay);
+        self.inputs = (1..=input_delay)
+            .map(|frame| (frame, FighterInput::default()))
+            .collect();
+        self.ended = None;
+    }
+
+    fn receive(&mut self, messages: Vec<Message>, round: u32) {
+        for message in messages {
+            if let Message::Inputs {
+                round: r,
+                start,
+                ack,
+                inputs,
+            } = message
+                && r == round
+            {
+                self.rollback.receive(start, &inputs, ack);
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        if self.ended.is_some() {
+            return;
+        }
+
+        let input = self.choose_input();
+        let frame = self.rollback.frame();
+        let events = self.rollback.advance(&mut self.bout, input);
+
+        if self.rollback.frame() > frame {
+            self.inputs
+                .insert(frame + 1 + self.rollback.input_delay(), input);
+        }
+
+        self.ended = events
+            .into_iter()
+            .find(|event| next_screen(*event).is_some());
+    }
+
+    /// mostly what the scripted computer player would do, with some surprises to get the
+    /// other machine guessing wrong.
+    fn choose_input(&mut self) -> FighterInput {
+        if !self.bout.tick.is_multiple_of(THINK_TICKS) || !self.bout.can_act(self.player) {
+            // keep holding whatever was held last (crouching).
+            self.inputs
+                .values()
+                .last()
+                .copied()
+                .unwrap_or_default()
+                .held()
+        } else if self.rng.next_f32() < RANDOM_INPUT_CHANCE {
+            ACTIONS[self.rng.below(ACTIONS.len())].into()
+        } else {
+            offensive(&self.bout, self.player)
+        }
+    }
+
+    fn send(&self, link: &mut Link, now: u64, round: u32) {
+        let (start, inputs) = self.rollback.outgoing();
+
+        link.send(
+            now,
+            &Message::Inputs {
+                round,
+                start,
+                ack: self.rollback.ack(),
+                inputs,
+            },
+        );
+    }
+}
+
+/// what happened over every bout.
+#[derive(Default)]
+struct Stats {
+    bouts: u64,
+    desyncs: u64,
+    timeouts: u64,
+    ticks: u64,
+    rollbacks: u64,
+    resimulated: u64,
+    stalls: u64,
+}
+
+fn main() {
+    let args = Args::parse().unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        exit(1);
+    });
+
+    let mut rng = Rng::new(args.seed);
+    let mut host = Peer::new(Player::One, args.input_delay, Rng::new(rng.next_u64()));
+    let mut client = Peer::new(Player::Two, args.input_delay, Rng::new(rng.next_u64()));
+    let mut to_client = Link::new(&args, Rng::new(rng.next_u64()));
+    let mut to_host = Link::new(&args, Rng::new(rng.next_u64()));
+    let mut reference = Bout::new();
+    let mut stats = Stats::default();
+    let max_ticks = (MAX_BOUT_SECONDS / TICK) as u64;
+
+    println!(
+        "fencing {} bouts over a link with {} (+0-{}) ticks of latency and {:.0}% loss, {} ticks \
+         of input delay (seed {})",
+        args.bouts,
+        args.latency,
+        args.jitter,
+        args.loss * 100.0,
+        args.input_delay,
+        args.seed
+    );
+
+    for round in 0..args.bouts as u32 {
+        let mut now = 0;
+
+        while (host.ended.is_none() || client.ended.is_none()) && now < max_ticks {
+            host.receive(to_host.deliver(now), round);
+            client.receive(to_client.deliver(now), round);
+            host.step();
+            client.step();
+            host.send(&mut to_client, now, round);
+            client.send(&mut to_host, now, round);
+            now += 1;
+        }
+
+        stats.bouts += 1;
+        stats.ticks += now;
+
+        for peer in [&host, &client] {
+            stats.rollbacks += peer.rollback.rollbacks;
+            stats.resimulated += peer.rollback.resimulated;
+            stats.stalls += peer.rollback.stalls;
+        }
+
+        if now >= max_ticks {
+            stats.timeouts += 1;
+        } else if let Some(problem) = check(&host, &client, &mut reference) {
+            stats.desyncs += 1;
+            eprintln!("bout {round} desynced: {problem}");
+        }
+
+        host.next_bout(args.input_delay);
+        client.next_bout(args.input_delay);
+        reference.reset();
+        // whatever is still on its way belongs to the last bout.
+        to_host.in_flight.clear();
+        to_client.in_flight.clear();
+    }
+
+    let n = stats.bouts.max(1) as f32;
+    println!(
+        "bouts {} | desyncs {} | timeouts {} | ticks/bout {:.0} | rollbacks/bout {:.1} (avg {:.1} ticks resimulated) | stalled ticks/bout {:.1}",
+        stats.bouts,
+        stats.desyncs,
+        stats.timeouts,
+        stats.ticks as f32 / n,
+        stats.rollbacks as f32 / n,
+        stats.resimulated as f32 / stats.rollbacks.max(1) as f32,
+        stats.stalls as f32 / n,
+    );
+
+    if stats.desyncs > 0 {
+        exit(1);
+    }
+}
+
+/// fences the bout again from both peers inputs, with nothing lost or late, and compares the
+/// result to what each peer ended up with. returns what went wrong, if anything did.
+fn check(host: &Peer, client: &Peer, reference: &mut Bout) -> Option<String> {
+    let mut ended = None;
+    let mut frame = 1;
+
+    while ended.is_none() {
+        let (Some(p1), Some(p2)) = (host.inputs.get(&frame), client.inputs.get(&frame)) else {
+            return Some(format!("no input recorded for tick {frame}"));
+        };
+
+        ended = reference
+            .step(*p1, *p2)
+            .into_iter()
+            .find(|event| next_screen(*event).is_some());
+        frame += 1;
+    }
+
+    if host.ended != ended || client.ended != ended {
+        return Some(format!(
+            "ended with {:?} (host) and {:?} (client), should have been {ended:?}",
+            host.ended, client.ended
+        ));
+    }
+
+    let expected = format!("{reference:?}");
+
+    for (name, peer) in [("host", host), ("client", client)] {
+        let got = peer
+            .rollback
+            .confirmed_bout()
+            .map(|bout| format!("{bout:?}"));
+
+        if got.as_ref() != Some(&expected) {
+            return Some(format!(
+                "the {name} ended with {got:?}, should have been {expected}"
+            ));
+        }
+    }
+
+    None
+}
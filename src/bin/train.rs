@@ -0,0 +1,237 @@
+//! trains the q-learning computer player without a window, as fast as the cpu allows.
+//!
+//! ```sh
+//! cargo run --release --bin train -- --episodes 100000 --opponent scripted
+//! ```
+use fencing_game::{
+    ai::{
+        offensive,
+        q_learning::{q_table_path, reward, QAgent, QMode},
+        THINK_TICKS,
+    },
+    bout::{Bout, BoutEvent, FighterInput, TICK},
+    fighter::Player,
+    rng::Rng,
+};
+use std::{env, path::PathBuf, process::exit, time::Instant};
+
+/// bouts that drag on longer than this (in seconds) are called off with no touch.
+const MAX_BOUT_SECONDS: f32 = 60.0;
+
+const USAGE: &str = "usage: train [--episodes N] [--report N] [--opponent scripted|self] \
+[--seed N] [--alpha N] [--gamma N] [--epsilon N] [--fresh] [--out PATH]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opponent {
+    /// the scripted, offensive, computer player.
+    Scripted,
+    /// a frozen copy of the agent, refreshed after every report.
+    SelfPlay,
+}
+
+struct Args {
+    episodes: u64,
+    report: u64,
+    opponent: Opponent,
+    seed: u64,
+    alpha: Option<f32>,
+    gamma: Option<f32>,
+    epsilon: Option<f32>,
+    fresh: bool,
+    out: PathBuf,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Self {
+            episodes: 10_000,
+            report: 1_000,
+            opponent: Opponent::Scripted,
+            seed: Rng::from_time().next_u64(),
+            alpha: None,
+            gamma: None,
+            epsilon: None,
+            fresh: false,
+            out: q_table_path(),
+        };
+        let mut argv = env::args().skip(1);
+
+        while let Some(arg) = argv.next() {
+            let mut value = || argv.next().ok_or(format!("{arg} needs a value"));
+
+            match arg.as_str() {
+                "--episodes" => args.episodes = parse(&value()?)?,
+                "--report" => args.report = parse::<u64>(&value()?)?.max(1),
+                "--opponent" => {
+                    args.opponent = match value()?.as_str() {
+                        "scripted" => Opponent::Scripted,
+                        "self" => Opponent::SelfPlay,
+                        other => return Err(format!("unknown opponent: {other}")),
+                    }
+                }
+                "--seed" => args.seed = parse(&value()?)?,
+                "--alpha" => args.alpha = Some(parse(&value()?)?),
+                "--gamma" => args.gamma = Some(parse(&value()?)?),
+                "--epsilon" => args.epsilon = Some(parse(&value()?)?),
+                "--fresh" => args.fresh = true,
+                "--out" => args.out = PathBuf::from(value()?),
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    exit(0);
+                }
+                other => return Err(format!("unknown argument: {other}")),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("could not parse \"{value}\""))
+}
+
+/// what happened over a run of episodes.
+#[derive(Default)]
+struct Stats {
+    episodes: u64,
+    agent_touches: u64,
+    opponent_touches: u64,
+    reward: f32,
+}
+
+impl Stats {
+    fn report(&self, first: u64, agent: &QAgent) {
+        let n = self.episodes.max(1) as f32;
+
+        println!(
+            "episodes {:>7}-{:<7} | win rate {:>5.1}% | touches/bout {:.2} (agent {:.2}, opponent {:.2}) | avg reward {:>6.3} | epsilon {:.3}",
+            first,
+            first + self.episodes - 1,
+            self.agent_touches as f32 / n * 100.0,
+            (self.agent_touches + self.opponent_touches) as f32 / n,
+            self.agent_touches as f32 / n,
+            self.opponent_touches as f32 / n,
+            self.reward / n,
+            agent.epsilon,
+        );
+    }
+}
+
+fn main() {
+    let args = Args::parse().unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        exit(1);
+    });
+
+    let rng = Rng::new(args.seed);
+    let mut agent = if args.fresh {
+        QAgent::new(QMode::Training, rng)
+    } else {
+        QAgent::load_or_new(&args.out, QMode::Training, rng)
+    };
+    agent.alpha = args.alpha.unwrap_or(agent.alpha);
+    agent.gamma = args.gamma.unwrap_or(agent.gamma);
+    agent.epsilon = args.epsilon.unwrap_or(agent.epsilon);
+
+    let mut rival = frozen(&agent);
+    let mut bout = Bout::new();
+    let mut stats = Stats::default();
+    let started = Instant::now();
+
+    println!(
+        "training for {} episodes against {:?} (seed {})",
+        args.episodes, args.opponent, args.seed
+    );
+
+    for episode in 0..args.episodes {
+        bout.reset();
+        bout.state.reset_scores();
+
+        let (touch, episode_reward) = run_episode(&mut bout, &mut agent, &mut rival, args.opponent);
+
+        stats.episodes += 1;
+        stats.reward += episode_reward;
+        match touch {
+            Some(Player::Two) => stats.agent_touches += 1,
+            Some(Player::One) => stats.opponent_touches += 1,
+            None => {}
+        }
+
+        if (episode + 1) % args.report == 0 || episode + 1 == args.episodes {
+            stats.report(episode + 1 - stats.episodes, &agent);
+            stats = Stats::default();
+            rival = frozen(&agent);
+
+            if let Err(e) = agent.save(&args.out) {
+                eprintln!("could not save q-table to {}: {e}", args.out.display());
+            }
+        }
+    }
+
+    println!(
+        "done in {:.1?}, q-table written to {}",
+        started.elapsed(),
+        args.out.display()
+    );
+}
+
+/// a copy of the agent that plays its best moves and does not learn.
+fn frozen(agent: &QAgent) -> QAgent {
+    let mut rival = agent.clone();
+    rival.mode = QMode::Inference;
+
+    rival
+}
+
+/// fences a single bout, the agent is always player two. returns who scored (if anyone) and the
+/// total reward the agent collected.
+fn run_episode(
+    bout: &mut Bout,
+    agent: &mut QAgent,
+    rival: &mut QAgent,
+    opponent: Opponent,
+) -> (Option<Player>, f32) {
+    let max_ticks = (MAX_BOUT_SECONDS / TICK) as u64;
+    let mut touch = None;
+    let mut total_reward = 0.0;
+    let mut p1_input = FighterInput::default();
+    let mut p2_input = FighterInput::default();
+
+    while !bout.over && bout.tick < max_ticks {
+        let thinking = bout.tick.is_multiple_of(THINK_TICKS);
+
+        // between decisions the fighters keep holding what they held (crouching).
+        p1_input = if thinking && bout.can_act(Player::One) {
+            match opponent {
+                Opponent::Scripted => offensive(bout, Player::One),
+                Opponent::SelfPlay => rival.act(bout, Player::One),
+            }
+        } else {
+            p1_input.held()
+        };
+        p2_input = if thinking && bout.can_act(Player::Two) {
+            agent.act(bout, Player::Two)
+        } else {
+            p2_input.held()
+        };
+
+        let events = bout.step(p1_input, p2_input);
+        total_reward += reward(Player::Two, &events);
+        agent.observe(bout, Player::Two, &events);
+
+        for event in events {
+            if let BoutEvent::Touch(scorer, _) = event {
+                touch = Some(scorer);
+            }
+        }
+    }
+
+    // if the bout timed out, learn from how it ended anyway.
+    agent.end_episode();
+    rival.abandon_episode();
+
+    (touch, total_reward)
+}
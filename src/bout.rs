@@ -0,0 +1,1158 @@
+use crate::{
+    distance,
+    fighter::*,
+    rng::Rng,
+    state::{GameState, Riposte, Screen},
+};
+use bevy::prelude::*;
+
+/// how long (in seconds) a single simulation tick lasts.
+pub const TICK: f32 = 1.0 / 64.0;
+/// how far (in meters) from the center of the piste a fighter may go before being stopped.
+pub const PISTE_BOUND: f32 = 7.0;
+/// how close (in meters) the fighters must be for a lunge to land.
+pub const HIT_RANGE: f32 = 0.5;
+/// how close (in meters) the fighters must be for a fleche to land, it has to run into them.
+pub const FLECHE_RANGE: f32 = 0.35;
+/// how close (in meters) the fighters must be for a balestra to land.
+pub const BALESTRA_RANGE: f32 = 0.65;
+/// how close (in meters) the fighters must be for a riposte to land.
+pub const RIPOSTE_RANGE: f32 = 1.0;
+/// how long (in seconds) after a parry the parrier has to start their riposte.
+pub const RIPOSTE_WINDOW: f32 = 0.5;
+/// how close (in meters) the fighters must be for a beat to reach the opponents blade.
+pub const BEAT_RANGE: f32 = 1.0;
+/// how fast a crouched fighter moves, as a fraction of their normal speed.
+pub const CROUCH_SPEED: f32 = 0.5;
+/// how much further (in meters) an attack from the offensive stance reaches.
+pub const OFFENCE_REACH: f32 = 0.1;
+/// how much quicker (in seconds) a lunge from the offensive stance is recovered from.
+pub const OFFENCE_RECOVERY: f32 = 0.15;
+/// how long (in seconds) an attack on a fighter in the defensive stance has to go on before it
+/// can land, extra time for them to parry.
+pub const DEFENCE_PARRY: f32 = 0.1;
+/// how fast a fighter in the defensive stance retreats, as a fraction of their normal speed.
+pub const DEFENCE_RETREAT: f32 = 1.25;
+
+/// what a fighter wants to do this tick. produced by the keyboard, the computer player, or
+/// anything else that can drive a fighter.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FighterInput {
+    /// the footwork or attack to start. ignored while the fighter is blocked by another action.
+    pub action: Option<Move>,
+    /// the gaurd to switch to.
+    pub gaurd: Option<Gaurd>,
+    /// wether the fighter wants to crouch. held, like a key, so it is kept untill an input
+    /// without it.
+    pub crouch: bool,
+    /// the stance to switch to, Offence or Defence. ignored while the fighter is blocked.
+    pub stance: Option<Stance>,
+}
+
+impl FighterInput {
+    /// what to keep sending between decisions: stay crouched (or not), start nothing new.
+    pub fn held(&self) -> Self {
+        Self {
+            crouch: self.crouch,
+            ..default()
+        }
+    }
+}
+
+/// things that happened during a tick of the simulation.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoutEvent {
+    /// the player scored a touch, the screen is where the game should go next.
+    Touch(Player, Screen),
+    /// the player parried the opponents lunge and stole right of way.
+    Parry(Player),
+    /// the player beat the opponents blade aside and stole right of way.
+    Beat(Player),
+    /// the fighters moved passed each other.
+    SideFlip,
+    /// both attacks landed at once, with neither fighter having right of way. no touch.
+    Simultaneous,
+    /// the clock ran out, the screen is where the game should go next.
+    TimeUp(Screen),
+    /// the player tried to leave the piste and was stopped at the edge.
+    OutOfBounds(Player),
+}
+
+/// the inputs both fighters used on a tick that can no longer change. sent once per tick, in
+/// order, so the bout can be recorded.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct TickInputs {
+    pub p1: FighterInput,
+    pub p2: FighterInput,
+}
+
+/// a headless simulation of a bout between two fighters. it knows nothing about windows,
+/// keyboards, or sprites; it only advances by fixed ticks from each fighters input.
+#[derive(Resource, Debug, Clone)]
+pub struct Bout {
+    pub p1: Fighter,
+    pub p2: Fighter,
+    pub state: GameState,
+    /// how many ticks have been simulated since the bout started.
+    pub tick: u64,
+    /// set once a touch is scored or the fighters switch sides. no more ticks get simulated
+    /// until the bout is reset.
+    pub over: bool,
+}
+
+impl Default for Bout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bout {
+    pub fn new() -> Self {
+        Self {
+            p1: Fighter::new(Player::One),
+            p2: Fighter::new(Player::Two),
+            state: GameState::new(),
+            tick: 0,
+            over: false,
+        }
+    }
+
+    /// puts both fighters back on their en garde lines, keeps the score, who controls them,
+    /// which hand they fence with, and the stance they picked.
+    pub fn reset(&mut self) {
+        let controllers = (self.p1.controller, self.p2.controller);
+        let hands = (self.p1.handed, self.p2.handed);
+        let stances = (self.p1.en_garde, self.p2.en_garde);
+
+        self.p1 = Fighter::new(Player::One);
+        self.p2 = Fighter::new(Player::Two);
+        (self.p1.controller, self.p2.controller) = controllers;
+        (self.p1.handed, self.p2.handed) = hands;
+        self.p1.set_stance(stances.0);
+        self.p2.set_stance(stances.1);
+        self.state.reset();
+        self.tick = 0;
+        self.over = false;
+    }
+
+    pub fn fighter(&self, player: Player) -> &Fighter {
+        match player {
+            Player::One => &self.p1,
+            Player::Two => &self.p2,
+        }
+    }
+
+    /// the players whose fighters are controlled by `controller`, player one first.
+    pub fn players(&self, controller: Controller) -> Vec<Player> {
+        [&self.p1, &self.p2]
+            .into_iter()
+            .filter(|fighter| fighter.controller == controller)
+            .map(|fighter| fighter.player)
+            .collect()
+    }
+
+    /// wether the fighter controlled by `player` can do anything this tick. a fighter stuck in
+    /// an action can still try to parry a riposte.
+    pub fn can_act(&self, player: Player) -> bool {
+        !self.fighter(player).action.blocked()
+            || self.fighter(player.other()).action.act == Move::Riposte
+    }
+
+    /// returns the fighter controlled by player, their opponent, and the game state.
+    fn split(&mut self, player: Player) -> (&mut Fighter, &mut Fighter, &mut GameState) {
+        match player {
+            Player::One => (&mut self.p1, &mut self.p2, &mut self.state),
+            Player::Two => (&mut self.p2, &mut self.p1, &mut self.state),
+        }
+    }
+
+    /// advances the bout by one tick.
+    pub fn step(&mut self, p1_input: FighterInput, p2_input: FighterInput) -> Vec<BoutEvent> {
+        let mut events = Vec::new();
+
+        if self.over {
+            return events;
+        }
+
+        self.tick += 1;
+
+        self.footwork(Player::One, p1_input, &mut events);
+        self.footwork(Player::Two, p2_input, &mut events);
+        self.blade_play(Player::One, p1_input, &mut events);
+        self.blade_play(Player::Two, p2_input, &mut events);
+        self.move_fighters();
+        self.bounds_limiter(&mut events);
+        self.touch_scored(&mut events);
+        self.riposte_window();
+        self.side_flip_detect(&mut events);
+        self.run_clock(&mut events);
+
+        events
+    }
+
+    fn footwork(&mut self, player: Player, input: FighterInput, events: &mut Vec<BoutEvent>) {
+        let (fighter, opponent, state) = self.split(player);
+
+        // with a riposte to throw, the attack button throws it. it is thrown with the arm alone,
+        // so it cuts short whatever the fighter was doing.
+        let attacking = matches!(input.action, Some(Move::Lunge | Move::Riposte));
+
+        if let Some(riposte) = state
+            .riposte
+            .as_mut()
+            .filter(|riposte| attacking && riposte.player == player && !riposte.thrown)
+        {
+            fighter.action = Action::from(Move::Riposte);
+            riposte.thrown = true;
+        }
+
+        if fighter.action.blocked() {
+            // tapping advance (or retreat) again mid step turns it into a double one.
+            if input.action.is_some_and(|act| fighter.double_step(act)) {
+                debug!("player {:?} doubled their step", player);
+            }
+
+            return;
+        }
+
+        if fighter.crouched != input.crouch {
+            debug!("player {:?} crouched: {}", player, input.crouch);
+            fighter.crouched = input.crouch;
+        }
+
+        if let Some(stance) = input.stance.filter(|stance| *stance != fighter.en_garde) {
+            debug!("player {:?} stance change: {:?}", player, stance);
+            fighter.set_stance(stance);
+        }
+
+        match input.action {
+            Some(act @ (Move::Retreat | Move::DoubleRetreat)) => {
+                fighter.set_action(act);
+
+                if state.row == Some(player) {
+                    state.row = None;
+                }
+            }
+            Some(act @ (Move::Advance | Move::DoubleAdvance)) => {
+                fighter.set_action(act);
+
+                if state.row.is_none() {
+                    state.row = Some(player);
+                }
+            }
+            // a riposte can only be thrown after a parry.
+            Some(Move::Riposte) => {}
+            Some(act @ (Move::Lunge | Move::Fleche | Move::Balestra)) => {
+                fighter.set_action(act);
+                state.lunge(player);
+
+                if act.lunge() {
+                    if fighter.en_garde == Stance::Offence {
+                        fighter.action.block_for -= OFFENCE_RECOVERY;
+                    }
+
+                    fighter.stance = Stance::Lunged;
+                }
+            }
+            Some(Move::Beat) => {
+                fighter.set_action(Move::Beat);
+
+                // a beat meets the opponents blade in line, a lunge is parried instead. a beat
+                // can't be beaten back untill it is done.
+                if state.row == Some(player.other())
+                    && !opponent.attacking()
+                    && !opponent.beating()
+                    && fighter.parries(opponent)
+                    && distance(fighter.position, opponent.position) <= BEAT_RANGE
+                {
+                    info!("player {:?} beat the blade and stole right of way", player);
+                    state.row = Some(player);
+                    events.push(BoutEvent::Beat(player));
+                }
+            }
+            Some(Move::EnGarde) | None => {}
+        }
+    }
+
+    fn blade_play(&mut self, player: Player, input: FighterInput, events: &mut Vec<BoutEvent>) {
+        if !self.can_act(player) {
+            return;
+        }
+
+        let (fighter, opponent, state) = self.split(player);
+
+        let prev_gaurd = fighter.gaurd;
+
+        if let Some(gaurd) = input.gaurd {
+            fighter.gaurd = gaurd;
+        }
+
+        if prev_gaurd != fighter.gaurd {
+            debug!("player {:?} gaurd change: {:?}", player, fighter.gaurd);
+        }
+
+        if prev_gaurd != fighter.gaurd && opponent.attacking() && fighter.parries(opponent) {
+            fighter.parrying = true;
+        } else if !(opponent.attacking() && fighter.parries(opponent)) {
+            fighter.parrying = false;
+        }
+
+        // parrying a riposte is a counter-parry, and earns a counter-riposte.
+        let riposted = opponent.action.act == Move::Riposte
+            && state
+                .riposte
+                .is_some_and(|riposte| riposte.player == player.other());
+
+        if (Some(player.other()) == state.lunger || riposted)
+            && prev_gaurd != fighter.gaurd
+            && fighter.parries(opponent)
+        {
+            info!("player {:?} parried and stole right of way", player);
+            // the parried blade is knocked out of line, it has to come back to parry again.
+            opponent.gaurd = opponent.gaurd.opposite();
+            state.lunger = None;
+            state.row = Some(player);
+            state.riposte = Some(Riposte {
+                player,
+                ticks_left: (RIPOSTE_WINDOW / TICK) as u16,
+                parries: match state.riposte {
+                    Some(riposte) if riposted => riposte.parries + 1,
+                    _ => 1,
+                },
+                thrown: false,
+            });
+            events.push(BoutEvent::Parry(player));
+        }
+    }
+
+    fn move_fighters(&mut self) {
+        for fighter in [&mut self.p1, &mut self.p2] {
+            let retreating = matches!(fighter.action.act, Move::Retreat | Move::DoubleRetreat);
+            let pos_d = fighter.update_movement(TICK);
+            let mut speed = if fighter.crouched { CROUCH_SPEED } else { 1.0 };
+
+            if retreating && fighter.stance == Stance::Defence {
+                speed *= DEFENCE_RETREAT;
+            }
+
+            fighter.position += pos_d * speed * fighter.player.facing();
+        }
+    }
+
+    fn bounds_limiter(&mut self, events: &mut Vec<BoutEvent>) {
+        if self.p1.position < -PISTE_BOUND {
+            info!("player 1 tried to move out of bounds, limiting movement.");
+            self.p1.position = -PISTE_BOUND;
+            events.push(BoutEvent::OutOfBounds(Player::One));
+        }
+
+        if self.p2.position > PISTE_BOUND {
+            info!("player 2 tried to move out of bounds, limiting movement.");
+            self.p2.position = PISTE_BOUND;
+            events.push(BoutEvent::OutOfBounds(Player::Two));
+        }
+    }
+
+    /// wether the attack of the fighter controlled by `player` reaches the opponent this tick,
+    /// right of way or not.
+    fn lands(&self, player: Player) -> bool {
+        let fighter = self.fighter(player);
+        let opponent = self.fighter(player.other());
+
+        // a riposte has to be thrown in time, and only lands once the arm is out.
+        let in_time = fighter.action.act != Move::Riposte
+            || (self
+                .state
+                .riposte
+                .is_some_and(|riposte| riposte.player == player)
+                && fighter.extended());
+
+        // a defensive opponent gets a moment longer to parry.
+        let parry_time = if opponent.stance == Stance::Defence {
+            DEFENCE_PARRY
+        } else {
+            0.0
+        };
+        let reach = if fighter.en_garde == Stance::Offence {
+            OFFENCE_REACH
+        } else {
+            0.0
+        };
+
+        fighter.attacking()
+            && in_time
+            && !opponent.parrying
+            && fighter.reaches(opponent)
+            && fighter.action.elapsed() >= parry_time
+            && distance(fighter.position, opponent.position)
+                <= hit_range(fighter.action.act) + reach
+    }
+
+    /// wether a touch from `player` counts, they need right of way and to be the one who
+    /// attacked (a riposte needs no lunge).
+    fn priority(&self, player: Player) -> bool {
+        let fighter = self.fighter(player);
+        let opponent = self.fighter(player.other());
+        // attacks started on the same tick are both first, whichever was taken first.
+        let together = opponent.attacking()
+            && (fighter.action.elapsed() - opponent.action.elapsed()).abs() < TICK / 2.0;
+
+        self.state.row == Some(player)
+            && (fighter.action.act == Move::Riposte
+                || self.state.lunger == Some(player)
+                || together)
+    }
+
+    /// awards at most one touch. when both attacks land, right of way decides, and with
+    /// neither fighter having it, no touch is given.
+    fn touch_scored(&mut self, events: &mut Vec<BoutEvent>) {
+        if !(self.p1.attacking() || self.p2.attacking()) {
+            return;
+        }
+
+        debug!(
+            "{}, {}, distance => {}",
+            self.p1.position,
+            self.p2.position,
+            distance(self.p1.position, self.p2.position)
+        );
+
+        let landed: Vec<Player> = [Player::One, Player::Two]
+            .into_iter()
+            .filter(|player| self.lands(*player))
+            .collect();
+
+        match landed.iter().find(|player| self.priority(**player)) {
+            Some(player) => {
+                info!(
+                    "Player {:?} scored with {}",
+                    player,
+                    self.state.phrase().label()
+                );
+                let next_state = self.state.score_touch(*player);
+                events.push(BoutEvent::Touch(*player, next_state));
+                self.over = true;
+            }
+            None if landed.len() == 2 => {
+                info!("simultaneous, no touch");
+                events.push(BoutEvent::Simultaneous);
+                self.over = true;
+            }
+            // an attack without right of way does not count.
+            None => {}
+        }
+    }
+
+    /// runs down the time left to start a riposte, and closes the chance once it runs out or
+    /// the riposte is done.
+    fn riposte_window(&mut self) {
+        let Some(riposte) = self.state.riposte.as_mut() else {
+            return;
+        };
+        let riposting = match riposte.player {
+            Player::One => self.p1.action.act == Move::Riposte,
+            Player::Two => self.p2.action.act == Move::Riposte,
+        };
+
+        if (riposte.thrown && !riposting) || (!riposte.thrown && riposte.ticks_left == 0) {
+            self.state.riposte = None;
+        } else if !riposte.thrown {
+            riposte.ticks_left -= 1;
+        }
+    }
+
+    /// a fleche runs passed the opponent, so the fighters are only reset once it is done.
+    fn side_flip_detect(&mut self, events: &mut Vec<BoutEvent>) {
+        if !self.over
+            && self.p1.position > self.p2.position
+            && !self.p1.attacking()
+            && !self.p2.attacking()
+        {
+            info!("players switched moved passed each other, resseting.");
+            events.push(BoutEvent::SideFlip);
+            self.over = true;
+        }
+    }
+
+    /// runs down the clock, and calls time once it runs out.
+    fn run_clock(&mut self, events: &mut Vec<BoutEvent>) {
+        if self.over || self.state.rules.period.is_none() {
+            return;
+        }
+
+        self.state.clock = self.state.clock.saturating_sub(1);
+
+        if self.state.clock > 0 {
+            return;
+        }
+
+        // the coin for priority is tossed from the bout itself, so every machine (and replay)
+        // tosses the same.
+        let seed = self.tick
+            ^ (u64::from(self.p1.position.to_bits()) << 32)
+            ^ u64::from(self.p2.position.to_bits());
+        let coin = if Rng::new(seed).below(2) == 0 {
+            Player::One
+        } else {
+            Player::Two
+        };
+
+        info!("time");
+        let next_state = self.state.time_up(coin);
+        events.push(BoutEvent::TimeUp(next_state));
+        self.over = true;
+    }
+}
+
+/// how close (in meters) the fighters must be for `attack` to land.
+fn hit_range(attack: Move) -> f32 {
+    match attack {
+        Move::Fleche => FLECHE_RANGE,
+        Move::Balestra => BALESTRA_RANGE,
+        Move::Riposte => RIPOSTE_RANGE,
+        _ => HIT_RANGE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{MatchRules, Phrase};
+
+    /// a bout with the fighters `gap` meters apart, around the center of the piste.
+    fn bout_at(gap: f32) -> Bout {
+        let mut bout = Bout::new();
+        bout.p1.position = -gap / 2.0;
+        bout.p2.position = gap / 2.0;
+        bout
+    }
+
+    fn doing(act: Move) -> FighterInput {
+        FighterInput {
+            action: Some(act),
+            ..default()
+        }
+    }
+
+    fn gaurd(gaurd: Gaurd) -> FighterInput {
+        FighterInput {
+            gaurd: Some(gaurd),
+            ..default()
+        }
+    }
+
+    /// steps the bout with inputs from `p1` and `p2` untill it is over, or `ticks` have passed.
+    /// returns everything that happened.
+    fn fence(
+        bout: &mut Bout,
+        ticks: u64,
+        mut p1: impl FnMut(&Bout) -> FighterInput,
+        mut p2: impl FnMut(&Bout) -> FighterInput,
+    ) -> Vec<BoutEvent> {
+        let mut events = Vec::new();
+
+        for _ in 0..ticks {
+            if bout.over {
+                break;
+            }
+
+            let inputs = (p1(bout), p2(bout));
+            events.extend(bout.step(inputs.0, inputs.1));
+        }
+
+        events
+    }
+
+    fn touches(events: &[BoutEvent]) -> Vec<Player> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                BoutEvent::Touch(player, _) => Some(*player),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lunge_with_right_of_way_scores() {
+        let mut bout = Bout::new();
+        let events = fence(
+            &mut bout,
+            64 * 10,
+            |bout| match distance(bout.p1.position, bout.p2.position) {
+                _ if !bout.can_act(Player::One) => FighterInput::default(),
+                gap if gap > 1.0 => doing(Move::Advance),
+                _ => doing(Move::Lunge),
+            },
+            |_| FighterInput::default(),
+        );
+
+        assert_eq!(touches(&events), vec![Player::One]);
+        assert!(bout.over);
+        assert_eq!(bout.state.p1_score.touches, 1);
+    }
+
+    #[test]
+    fn lunge_without_right_of_way_does_not_score() {
+        let mut bout = bout_at(1.0);
+        bout.state.row = Some(Player::Two);
+
+        // one lunge, and time for it to finish.
+        let events = fence(
+            &mut bout,
+            64,
+            |bout| match bout.tick {
+                0 => doing(Move::Lunge),
+                _ => FighterInput::default(),
+            },
+            |_| FighterInput::default(),
+        );
+
+        assert!(touches(&events).is_empty());
+        assert!(!bout.over);
+        assert_eq!(bout.state.p1_score.touches, 0);
+    }
+
+    #[test]
+    fn matching_gaurd_parries() {
+        let mut bout = bout_at(1.0);
+        bout.state.row = Some(Player::One);
+        bout.p2.gaurd = Gaurd::Right;
+
+        let events = fence(
+            &mut bout,
+            64 * 2,
+            |_| doing(Move::Lunge),
+            // switch into the attackers line once the lunge is coming in.
+            |bout| {
+                if bout.p1.attacking() {
+                    gaurd(Gaurd::Left)
+                } else {
+                    FighterInput::default()
+                }
+            },
+        );
+
+        assert_eq!(events.first(), Some(&BoutEvent::Parry(Player::Two)));
+        assert!(!touches(&events).contains(&Player::One));
+        assert_eq!(bout.state.row, Some(Player::Two));
+    }
+
+    #[test]
+    fn passing_the_opponent_side_flips() {
+        let mut bout = bout_at(0.1);
+
+        let events = fence(
+            &mut bout,
+            64,
+            |_| doing(Move::Advance),
+            |_| FighterInput::default(),
+        );
+
+        assert_eq!(events, vec![BoutEvent::SideFlip]);
+        assert!(bout.over);
+    }
+
+    #[test]
+    fn piste_end_stops_the_fighter() {
+        let mut bout = Bout::new();
+        bout.p1.position = -PISTE_BOUND + 0.05;
+
+        let events = fence(
+            &mut bout,
+            64,
+            |_| doing(Move::Retreat),
+            |_| FighterInput::default(),
+        );
+
+        assert!(events.contains(&BoutEvent::OutOfBounds(Player::One)));
+        assert_eq!(bout.p1.position, -PISTE_BOUND);
+        assert!(!bout.over);
+    }
+
+    /// player one lunges from 1 meter away with right of way, in the line of `line`, at a player
+    /// two that crouches for the whole bout, or not.
+    fn lunge_at(line: Gaurd, crouched: bool) -> Vec<BoutEvent> {
+        let mut bout = bout_at(1.0);
+        bout.state.row = Some(Player::One);
+        bout.p1.gaurd = line;
+        // out of the way of the lunge, so it can't be parried.
+        bout.p2.gaurd = line.opposite();
+
+        fence(
+            &mut bout,
+            64,
+            |bout| match bout.tick {
+                0 => doing(Move::Lunge),
+                _ => FighterInput::default(),
+            },
+            |_| FighterInput {
+                crouch: crouched,
+                ..default()
+            },
+        )
+    }
+
+    #[test]
+    fn crouching_ducks_high_attacks() {
+        assert_eq!(touches(&lunge_at(Gaurd::Left, false)), vec![Player::One]);
+        assert!(touches(&lunge_at(Gaurd::Left, true)).is_empty());
+        assert!(touches(&lunge_at(Gaurd::Up, true)).is_empty());
+    }
+
+    #[test]
+    fn low_attacks_reach_crouched_fighters() {
+        assert_eq!(touches(&lunge_at(Gaurd::Down, true)), vec![Player::One]);
+    }
+
+    #[test]
+    fn crouched_fighters_move_slower() {
+        let advance = |crouch| {
+            let mut bout = Bout::new();
+            let start = bout.p1.position;
+            let input = FighterInput {
+                action: Some(Move::Advance),
+                crouch,
+                ..default()
+            };
+            bout.step(input, FighterInput::default());
+
+            for _ in 0..32 {
+                bout.step(input.held(), FighterInput::default());
+            }
+
+            bout.p1.position - start
+        };
+
+        assert!(advance(false) > 0.1);
+        assert!((advance(true) - advance(false) * CROUCH_SPEED).abs() < 1e-4);
+    }
+
+    /// player one, in `attacker` stance, lunges with right of way from `gap` meters at player two,
+    /// in `defender` stance, who switches to a parry after `parry_after` ticks (if any). returns
+    /// what happened, and the tick it ended on.
+    fn lunge_on_stances(
+        gap: f32,
+        attacker: Stance,
+        defender: Stance,
+        parry_after: Option<u64>,
+    ) -> (Vec<BoutEvent>, u64) {
+        let mut bout = bout_at(gap);
+        bout.state.row = Some(Player::One);
+        bout.p1.set_stance(attacker);
+        bout.p2.set_stance(defender);
+        bout.p2.gaurd = Gaurd::Right;
+
+        let events = fence(
+            &mut bout,
+            64 * 2,
+            |bout| match bout.tick {
+                0 => doing(Move::Lunge),
+                _ => FighterInput::default(),
+            },
+            |bout| match parry_after {
+                Some(ticks) if bout.tick >= ticks => gaurd(Gaurd::Left),
+                _ => FighterInput::default(),
+            },
+        );
+
+        (events, bout.tick)
+    }
+
+    #[test]
+    fn offensive_lunges_reach_further() {
+        let (offence, _) = lunge_on_stances(1.0, Stance::Offence, Stance::Offence, None);
+        let (defence, _) = lunge_on_stances(1.0, Stance::Defence, Stance::Offence, None);
+
+        assert_eq!(touches(&offence), vec![Player::One]);
+        assert!(touches(&defence).is_empty());
+    }
+
+    #[test]
+    fn defensive_fencers_get_longer_to_parry() {
+        let (events, offence_tick) = lunge_on_stances(0.3, Stance::Offence, Stance::Offence, None);
+        assert_eq!(touches(&events), vec![Player::One]);
+
+        let (events, defence_tick) = lunge_on_stances(0.3, Stance::Offence, Stance::Defence, None);
+        assert_eq!(touches(&events), vec![Player::One]);
+        assert!(defence_tick > offence_tick);
+        assert!(defence_tick as f32 * TICK >= DEFENCE_PARRY);
+
+        // a parry too late for an offensive fencer is in time for a defensive one.
+        let late = Some(offence_tick + 1);
+        let (events, _) = lunge_on_stances(0.3, Stance::Offence, Stance::Offence, late);
+        assert_eq!(touches(&events), vec![Player::One]);
+
+        let (events, _) = lunge_on_stances(0.3, Stance::Offence, Stance::Defence, late);
+        assert_eq!(events.first(), Some(&BoutEvent::Parry(Player::Two)));
+        assert!(touches(&events).is_empty());
+    }
+
+    #[test]
+    fn defensive_fencers_retreat_quicker() {
+        let retreat = |stance| {
+            let mut bout = Bout::new();
+            bout.p1.set_stance(stance);
+            let start = bout.p1.position;
+
+            for _ in 0..32 {
+                bout.step(doing(Move::Retreat), FighterInput::default());
+            }
+
+            start - bout.p1.position
+        };
+
+        assert!(retreat(Stance::Offence) > 0.1);
+        assert!(
+            (retreat(Stance::Defence) - retreat(Stance::Offence) * DEFENCE_RETREAT).abs() < 1e-4
+        );
+    }
+
+    /// a right handed player one lunges in their left line, player two, fencing with `handed`,
+    /// tries to parry it with `parry`.
+    fn parry_with(handed: Handed, parry: Gaurd) -> Vec<BoutEvent> {
+        let mut bout = bout_at(1.0);
+        bout.state.row = Some(Player::One);
+        bout.p2.handed = handed;
+        bout.p2.gaurd = Gaurd::Up;
+
+        fence(
+            &mut bout,
+            64 * 2,
+            |bout| match bout.tick {
+                0 => doing(Move::Lunge),
+                _ => FighterInput::default(),
+            },
+            |bout| match bout.p1.attacking() {
+                true => gaurd(parry),
+                false => FighterInput::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn lefties_parry_with_the_other_gaurd() {
+        assert_eq!(
+            parry_with(Handed::Right, Gaurd::Left).first(),
+            Some(&BoutEvent::Parry(Player::Two))
+        );
+        assert_eq!(
+            touches(&parry_with(Handed::Left, Gaurd::Left)),
+            vec![Player::One]
+        );
+        assert_eq!(
+            parry_with(Handed::Left, Gaurd::Right).first(),
+            Some(&BoutEvent::Parry(Player::Two))
+        );
+    }
+
+    #[test]
+    fn high_and_low_gaurds_are_the_same_for_either_hand() {
+        for gaurd in [Gaurd::Up, Gaurd::Down] {
+            assert!(gaurd.parries(Handed::Left, gaurd, Handed::Right));
+            assert!(gaurd.parries(Handed::Right, gaurd, Handed::Left));
+        }
+
+        assert!(Gaurd::Right.parries(Handed::Left, Gaurd::Left, Handed::Right));
+        assert!(!Gaurd::Left.parries(Handed::Left, Gaurd::Left, Handed::Right));
+        assert!(Gaurd::Left.parries(Handed::Left, Gaurd::Left, Handed::Left));
+    }
+
+    /// player one attacks with `attack` from `gap` meters away, with right of way.
+    fn attack_from(gap: f32, attack: Move) -> Vec<BoutEvent> {
+        let mut bout = bout_at(gap);
+        bout.state.row = Some(Player::One);
+        bout.p2.gaurd = Gaurd::Right;
+
+        fence(
+            &mut bout,
+            64 * 2,
+            |bout| match bout.tick {
+                0 => doing(attack),
+                _ => FighterInput::default(),
+            },
+            |_| FighterInput::default(),
+        )
+    }
+
+    #[test]
+    fn balestra_reaches_further_than_a_lunge() {
+        assert_eq!(touches(&attack_from(1.0, Move::Lunge)), vec![Player::One]);
+        assert!(touches(&attack_from(1.3, Move::Lunge)).is_empty());
+        assert_eq!(
+            touches(&attack_from(1.3, Move::Balestra)),
+            vec![Player::One]
+        );
+        assert!(touches(&attack_from(1.7, Move::Balestra)).is_empty());
+    }
+
+    #[test]
+    fn fleche_runs_passed_before_the_side_flip() {
+        // without right of way the fleche can't score, so it carries on passed.
+        let mut bout = bout_at(1.0);
+        bout.state.row = Some(Player::Two);
+        let mut input = doing(Move::Fleche);
+        let mut passed = false;
+        let mut landed = Vec::new();
+
+        while !bout.over && bout.tick < 64 * 2 {
+            let events = bout.step(input, FighterInput::default());
+            input = FighterInput::default();
+
+            if bout.p1.attacking() {
+                // the fencers cross while it is in flight, with no flip untill it is done.
+                passed |= bout.p1.position > bout.p2.position;
+                assert!(events.is_empty());
+            } else {
+                landed.extend(events);
+            }
+        }
+
+        assert!(passed);
+        assert_eq!(landed, vec![BoutEvent::SideFlip]);
+    }
+
+    /// a bout with player two holding right of way, `gap` meters from player one.
+    fn row_to_p2(gap: f32) -> Bout {
+        let mut bout = bout_at(gap);
+        bout.state.row = Some(Player::Two);
+        bout
+    }
+
+    #[test]
+    fn beat_steals_right_of_way() {
+        let mut bout = row_to_p2(BEAT_RANGE - 0.1);
+        let events = bout.step(doing(Move::Beat), FighterInput::default());
+
+        assert_eq!(events, vec![BoutEvent::Beat(Player::One)]);
+        assert_eq!(bout.state.row, Some(Player::One));
+    }
+
+    #[test]
+    fn beat_out_of_range_misses() {
+        let mut bout = row_to_p2(BEAT_RANGE + 0.1);
+        let events = bout.step(doing(Move::Beat), FighterInput::default());
+
+        assert!(events.is_empty());
+        assert_eq!(bout.state.row, Some(Player::Two));
+    }
+
+    #[test]
+    fn beat_in_the_wrong_line_misses() {
+        let mut bout = row_to_p2(BEAT_RANGE - 0.1);
+        bout.p2.gaurd = Gaurd::Up;
+        let events = bout.step(doing(Move::Beat), FighterInput::default());
+
+        assert!(events.is_empty());
+        assert_eq!(bout.state.row, Some(Player::Two));
+    }
+
+    #[test]
+    fn attacks_can_not_be_beaten() {
+        let mut bout = row_to_p2(BEAT_RANGE);
+        bout.step(FighterInput::default(), doing(Move::Lunge));
+        let events = bout.step(doing(Move::Beat), FighterInput::default());
+
+        assert!(events.is_empty());
+        assert_eq!(bout.state.row, Some(Player::Two));
+    }
+
+    /// wether `player` has a riposte to throw.
+    fn can_riposte(bout: &Bout, player: Player) -> bool {
+        bout.state
+            .riposte
+            .is_some_and(|riposte| riposte.player == player && !riposte.thrown)
+    }
+
+    /// player one lunges at player two, who parries it. the bout is left on the tick of the
+    /// parry.
+    fn parried_lunge() -> Bout {
+        let mut bout = bout_at(1.0);
+        bout.state.row = Some(Player::One);
+        bout.p2.gaurd = Gaurd::Right;
+
+        let lunge = bout.step(doing(Move::Lunge), FighterInput::default());
+        let parry = bout.step(FighterInput::default(), gaurd(Gaurd::Left));
+
+        assert!(lunge.is_empty());
+        assert_eq!(parry, vec![BoutEvent::Parry(Player::Two)]);
+        bout
+    }
+
+    #[test]
+    fn riposte_in_time_scores() {
+        let mut bout = parried_lunge();
+        assert!(can_riposte(&bout, Player::Two));
+
+        let events = fence(
+            &mut bout,
+            64,
+            |_| FighterInput::default(),
+            |bout| match can_riposte(bout, Player::Two) {
+                true => doing(Move::Riposte),
+                false => FighterInput::default(),
+            },
+        );
+
+        assert_eq!(touches(&events), vec![Player::Two]);
+        assert_eq!(bout.state.timeline[0].phrase, Phrase::Riposte);
+    }
+
+    #[test]
+    fn riposte_window_runs_out() {
+        let mut bout = parried_lunge();
+
+        // wait the window out, then try.
+        let window = (RIPOSTE_WINDOW / TICK) as u64;
+        let events = fence(
+            &mut bout,
+            window + 64,
+            |_| FighterInput::default(),
+            |bout| match bout.state.riposte {
+                None => doing(Move::Riposte),
+                Some(_) => FighterInput::default(),
+            },
+        );
+
+        assert!(touches(&events).is_empty());
+        assert_eq!(bout.state.riposte, None);
+        assert_ne!(bout.p2.action.act, Move::Riposte);
+    }
+
+    #[test]
+    fn counter_parry_earns_a_counter_riposte() {
+        let mut bout = parried_lunge();
+        // parry the riposte as it comes, then hit back.
+        let mut p1 = |bout: &Bout| {
+            if can_riposte(bout, Player::One) {
+                doing(Move::Riposte)
+            } else if bout.p2.action.act == Move::Riposte && !bout.p1.parrying {
+                gaurd(bout.p1.gaurd_against(&bout.p2))
+            } else {
+                FighterInput::default()
+            }
+        };
+        let mut p2 = |bout: &Bout| match can_riposte(bout, Player::Two) {
+            true => doing(Move::Riposte),
+            false => FighterInput::default(),
+        };
+
+        let mut events = Vec::new();
+
+        while bout
+            .state
+            .riposte
+            .is_none_or(|riposte| riposte.player == Player::Two)
+        {
+            assert!(
+                bout.tick < 64 && !bout.over,
+                "the riposte was never parried"
+            );
+            events.extend(bout.step(p1(&bout), p2(&bout)));
+        }
+
+        assert_eq!(events, vec![BoutEvent::Parry(Player::One)]);
+        assert_eq!(bout.state.riposte.map(|riposte| riposte.parries), Some(2));
+
+        let events = fence(&mut bout, 64, &mut p1, &mut p2);
+
+        assert_eq!(touches(&events), vec![Player::One]);
+        assert_eq!(bout.state.timeline[0].phrase, Phrase::CounterRiposte);
+    }
+
+    #[test]
+    fn simultaneous_attacks_score_for_right_of_way() {
+        let mut bout = bout_at(1.5);
+        bout.state.row = Some(Player::Two);
+
+        let events = fence(
+            &mut bout,
+            64 * 2,
+            |_| doing(Move::Lunge),
+            |_| doing(Move::Lunge),
+        );
+
+        assert_eq!(touches(&events), vec![Player::Two]);
+        assert!(!events.contains(&BoutEvent::Simultaneous));
+        assert_eq!(bout.state.p1_score.touches, 0);
+        assert_eq!(bout.state.p2_score.touches, 1);
+    }
+
+    #[test]
+    fn simultaneous_attacks_without_right_of_way_score_nothing() {
+        let mut bout = bout_at(1.5);
+
+        let events = fence(
+            &mut bout,
+            64 * 2,
+            |_| doing(Move::Lunge),
+            |_| doing(Move::Lunge),
+        );
+
+        assert_eq!(events, vec![BoutEvent::Simultaneous]);
+        assert!(bout.over);
+        assert_eq!(bout.state.p1_score.touches, 0);
+        assert_eq!(bout.state.p2_score.touches, 0);
+    }
+
+    #[test]
+    fn clock_running_out_calls_time() {
+        let mut bout = Bout::new();
+        bout.state.set_rules(MatchRules {
+            period: Some(180),
+            periods: 2,
+            ..default()
+        });
+        bout.state.clock = 3;
+
+        let events = fence(
+            &mut bout,
+            64,
+            |_| FighterInput::default(),
+            |_| FighterInput::default(),
+        );
+
+        assert_eq!(events, vec![BoutEvent::TimeUp(Screen::NewBout)]);
+        assert_eq!(bout.tick, 3);
+        assert_eq!(bout.state.period, 2);
+        assert!(bout.over);
+    }
+
+    #[test]
+    fn same_inputs_give_the_same_bout() {
+        const MOVES: [Option<Move>; 6] = [
+            None,
+            Some(Move::Advance),
+            Some(Move::Retreat),
+            Some(Move::Lunge),
+            Some(Move::Beat),
+            Some(Move::Fleche),
+        ];
+        const GAURDS: [Gaurd; 4] = [Gaurd::Right, Gaurd::Down, Gaurd::Left, Gaurd::Up];
+
+        let mut rng = Rng::new(7);
+        let mut random_input = || FighterInput {
+            action: MOVES[rng.below(MOVES.len())],
+            gaurd: Some(GAURDS[rng.below(GAURDS.len())]),
+            crouch: rng.below(8) == 0,
+            stance: None,
+        };
+        let inputs: Vec<(FighterInput, FighterInput)> = (0..64 * 30)
+            .map(|_| (random_input(), random_input()))
+            .collect();
+
+        let mut a = Bout::new();
+        let mut b = Bout::new();
+
+        for (p1, p2) in inputs {
+            assert_eq!(a.step(p1, p2), b.step(p1, p2));
+
+            if a.over {
+                a.reset();
+                b.reset();
+            }
+        }
+
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    }
+}
@@ -1,11 +1,15 @@
 use crate::{
     ai::Player2Marker,
-    distance,
-    fighter::*,
+    bout::{Bout, BoutEvent, FighterInput, TickInputs, TICK},
+    fighter::{Fighter, Move, Player, Stance},
     player::PlayerMarker,
-    state::{GameState, Screen},
+    state::{local_bout, GameMode, GameState, Handedness, MatchRules, Screen},
 };
 use bevy::{prelude::*, window::PrimaryWindow};
+use std::time::Duration;
+
+/// the pitch (in hz) of the click played when a blade is beaten aside.
+const BEAT_PITCH: f32 = 1760.0;
 
 #[derive(Component)]
 pub struct GaurdIconMarker;
@@ -16,102 +20,131 @@ pub struct PlayerScore;
 #[derive(Component)]
 pub struct ScoreBoard;
 
+/// the bout clock, under the score board.
+#[derive(Component)]
+pub struct ClockText;
+
+/// the inputs each fighter will use on the next tick of the bout.
+#[derive(Resource, Debug, Default)]
+pub struct BoutInputs {
+    pub p1: FighterInput,
+    pub p2: FighterInput,
+}
+
+impl BoutInputs {
+    pub fn get(&self, player: Player) -> FighterInput {
+        match player {
+            Player::One => self.p1,
+            Player::Two => self.p2,
+        }
+    }
+
+    pub fn get_mut(&mut self, player: Player) -> &mut FighterInput {
+        match player {
+            Player::One => &mut self.p1,
+            Player::Two => &mut self.p2,
+        }
+    }
+}
+
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, touch_scored.run_if(in_state(Screen::Game)))
-            .add_systems(Update, side_flip_detect.run_if(in_state(Screen::Game)))
-            .add_systems(Update, bounds_limiter.run_if(in_state(Screen::Game)))
+        app.init_resource::<BoutInputs>()
+            .init_resource::<Handedness>()
+            .init_resource::<MatchRules>()
+            .add_event::<BoutEvent>()
+            .add_event::<TickInputs>()
+            .add_systems(
+                FixedUpdate,
+                step_bout
+                    // the tutorial fences a bout of its own.
+                    .run_if(in_state(Screen::Game).or_else(in_state(Screen::HowToPlay)))
+                    // LAN bouts are stepped by the rollback session instead.
+                    .run_if(local_bout),
+            )
             .add_systems(OnExit(Screen::Game), rm_score_board)
-            .add_systems(OnExit(Screen::NewBout), score_board)
-            .add_systems(OnExit(Screen::NewBout), reset_world)
-            .add_systems(Update, position_fighters.run_if(in_state(Screen::Game)));
+            // the fighters stand at their lines for the countdown. the rules are set before the
+            // score board shows them.
+            .add_systems(OnEnter(Screen::NewBout), (reset_world, score_board).chain())
+            // it is taken down for the pause menu, and put back up as the bout goes on.
+            .add_systems(
+                OnTransition {
+                    from: Screen::PauseMenu,
+                    to: Screen::Game,
+                },
+                score_board,
+            )
+            .add_systems(
+                Update,
+                (beat_sound, show_clock).run_if(in_state(Screen::Game)),
+            )
+            .add_systems(
+                Update,
+                position_fighters.run_if(
+                    in_state(Screen::Game)
+                        .or_else(in_state(Screen::NewBout))
+                        .or_else(in_state(Screen::Replay))
+                        .or_else(in_state(Screen::HowToPlay)),
+                ),
+            );
     }
 }
 
-fn reset_world(mut world_state: ResMut<GameState>) {
-    world_state.reset();
-}
-
-fn touch_scored(
-    player1_query: Query<&Fighter, With<PlayerMarker>>,
-    player2_query: Query<&Fighter, Without<PlayerMarker>>,
-    mut world_state: ResMut<GameState>,
-    mut next_state: ResMut<NextState<Screen>>,
+pub fn reset_world(
+    mut bout: ResMut<Bout>,
+    mut inputs: ResMut<BoutInputs>,
+    game_mode: Res<GameMode>,
+    handedness: Res<Handedness>,
+    rules: Res<MatchRules>,
 ) {
-    if let (Ok(p1), Ok(p2)) = (player1_query.get_single(), player2_query.get_single())
-        && (p1.lunged() || p2.lunged())
-    {
-        let range = 0.5;
-        let distance = distance(p1.position, p2.position);
-
-        debug!("{}, {}, distance => {}", p1.position, p2.position, distance);
-        if p1.lunged()
-            && world_state.lunger == Some(Player::One)
-            && world_state.row == Some(Player::One)
-            && !p2.parrying
-            && distance <= range
-        {
-            info!("Player 1 scored");
-            let next_world_state = world_state.score_touch(Player::One);
-            // world_state.reset();
-            next_state.set(next_world_state);
-        }
-        if p2.lunged()
-            && world_state.lunger == Some(Player::Two)
-            && world_state.row == Some(Player::Two)
-            && !p1.parrying
-            && distance <= range
-        {
-            info!("Player 2 scored");
-            let next_world_state = world_state.score_touch(Player::Two);
-            // world_state.reset();
-            next_state.set(next_world_state);
-        }
-    }
-}
+    bout.reset();
 
-fn side_flip_detect(
-    player1_query: Query<&Fighter, With<PlayerMarker>>,
-    player2_query: Query<&Fighter, Without<PlayerMarker>>,
-    mut next_state: ResMut<NextState<Screen>>,
-) {
-    if let (Ok(p1), Ok(p2)) = (player1_query.get_single(), player2_query.get_single())
-        && p1.position > p2.position
-        && !p1.lunged()
-        && !p1.lunged()
-    {
-        info!("players switched moved passed each other, resseting.");
-        next_state.set(Screen::NewBout);
+    // over the LAN, the rules come from the host.
+    if game_mode.local() {
+        bout.state.set_rules(*rules);
     }
+
+    (bout.p1.controller, bout.p2.controller) = game_mode.controllers();
+    (bout.p1.handed, bout.p2.handed) = (handedness.p1, handedness.p2);
+    *inputs = BoutInputs::default();
 }
 
-fn bounds_limiter(
-    mut player1_query: Query<&mut Fighter, With<PlayerMarker>>,
-    mut player2_query: Query<&mut Fighter, Without<PlayerMarker>>,
+/// advances the bout by one tick using the inputs gathered for each fighter. the referee calls
+/// halt on whatever ends it.
+pub fn step_bout(
+    mut bout: ResMut<Bout>,
+    inputs: Res<BoutInputs>,
+    mut bout_events: EventWriter<BoutEvent>,
+    mut tick_inputs: EventWriter<TickInputs>,
 ) {
-    if let (Ok(mut p1), Ok(mut p2)) = (
-        player1_query.get_single_mut(),
-        player2_query.get_single_mut(),
-    ) {
-        let (p1_pos, p2_pos) = (p1.position, p2.position);
+    if !bout.over {
+        tick_inputs.send(TickInputs {
+            p1: inputs.p1,
+            p2: inputs.p2,
+        });
+    }
 
-        if p1_pos < -7.0 {
-            info!("player 1 tried to move out of bounds, limiting movement.");
-            p1.position = -7.0;
-        }
+    for event in bout.step(inputs.p1, inputs.p2) {
+        bout_events.send(event);
+    }
+}
 
-        if p2_pos > 7.0 {
-            info!("player 2 tried to move out of bounds, limiting movement.");
-            p2.position = 7.0;
+/// where the game goes after the event, if it ends the bout.
+pub fn next_screen(event: BoutEvent) -> Option<Screen> {
+    match event {
+        BoutEvent::Touch(_, next_world_state) | BoutEvent::TimeUp(next_world_state) => {
+            Some(next_world_state)
         }
+        BoutEvent::SideFlip | BoutEvent::Simultaneous => Some(Screen::NewBout),
+        BoutEvent::Parry(_) | BoutEvent::Beat(_) | BoutEvent::OutOfBounds(_) => None,
     }
 }
 
 fn position_fighters(
     mut player1_query: Query<
-        (&Fighter, &mut Transform),
+        (&mut Transform, &mut TextureAtlas),
         (
             With<PlayerMarker>,
             Without<Player2Marker>,
@@ -119,7 +152,7 @@ fn position_fighters(
         ),
     >,
     mut player2_query: Query<
-        (&Fighter, &mut Transform),
+        (&mut Transform, &mut TextureAtlas),
         (
             With<Player2Marker>,
             Without<PlayerMarker>,
@@ -143,10 +176,11 @@ fn position_fighters(
         ),
     >,
     window_query: Query<&Window, With<PrimaryWindow>>,
+    bout: Res<Bout>,
 ) {
     if let (
-        Ok((p1, mut p1_sprite)),
-        Ok((p2, mut p2_sprite)),
+        Ok((mut p1_sprite, mut p1_fighter_atlas)),
+        Ok((mut p2_sprite, mut p2_fighter_atlas)),
         Ok((mut p1_gaurd_sprite, mut p1_atlas)),
         Ok((mut p2_gaurd_sprite, mut p2_atlas)),
     ) = (
@@ -157,6 +191,7 @@ fn position_fighters(
     ) {
         let window = window_query.get_single().unwrap();
         // let piste_len = 14.0;
+        let (p1, p2) = (&bout.p1, &bout.p2);
         let (p1_pos, p2_pos) = (p1.position, p2.position);
 
         p1_sprite.translation = Vec3::new(
@@ -171,7 +206,9 @@ fn position_fighters(
             0.0,
         );
 
-        p1_atlas.index = p1.gaurd.into();
+        // the icons show the line covered, so a lefties gaurds are mirrored.
+        p1_atlas.index = p1.gaurd.for_hand(p1.handed).into();
+        p1_fighter_atlas.index = sprite_index(p1);
 
         p2_sprite.translation = Vec3::new(
             (window.width() / 2.0) + (32.0 * p2_pos),
@@ -185,22 +222,54 @@ fn position_fighters(
             0.0,
         );
 
-        p2_atlas.index = p2.gaurd.into();
+        p2_atlas.index = p2.gaurd.for_hand(p2.handed).into();
+        p2_fighter_atlas.index = sprite_index(p2);
     } else {
-        debug!(
-            "could not get one of: player1/2, player1/2 sprite, player1/2 gaurd icon sprite/atlas"
-        )
+        debug!("could not get one of: player1/2 sprite/atlas, player1/2 gaurd icon sprite/atlas")
     }
 }
 
-fn score_board(mut commands: Commands, world_state: Res<GameState>) {
+/// which frame of the fighter sprite sheet shows what the fighter is doing, and in which
+/// stance.
+fn sprite_index(fighter: &Fighter) -> usize {
+    match fighter.action.act {
+        Move::Beat => 2,
+        Move::Fleche => 4,
+        Move::Balestra => 5,
+        Move::Riposte => 6,
+        _ if fighter.stance == Stance::Lunged => 1,
+        _ if fighter.crouched => 3,
+        _ if fighter.stance == Stance::Defence => 7,
+        _ => 0,
+    }
+}
+
+/// plays a short click when a blade is beaten aside.
+fn beat_sound(
+    mut commands: Commands,
+    mut pitch_assets: ResMut<Assets<Pitch>>,
+    mut bout_events: EventReader<BoutEvent>,
+) {
+    for event in bout_events.read() {
+        if let BoutEvent::Beat(_) = event {
+            commands.spawn(PitchBundle {
+                source: pitch_assets.add(Pitch::new(BEAT_PITCH, Duration::from_millis(60))),
+                settings: PlaybackSettings::DESPAWN,
+            });
+        }
+    }
+}
+
+fn score_board(mut commands: Commands, bout: Res<Bout>) {
     commands.spawn((
         // Create a TextBundle that has a Text with a single section.
         TextBundle::from_section(
             // Accepts a `String` or any type that converts into a `String`, such as `&str`
             format!(
-                "({}/15/{})",
-                world_state.p1_score.touches, world_state.p1_score.matches
+                "({}/{}/{})",
+                bout.state.p1_score.touches,
+                bout.state.rules.touches_to_win,
+                bout.state.p1_score.matches
             ),
             TextStyle {
                 // This font is loaded and will be used instead of the default font.
@@ -226,8 +295,10 @@ fn score_board(mut commands: Commands, world_state: Res<GameState>) {
         TextBundle::from_section(
             // Accepts a `String` or any type that converts into a `String`, such as `&str`
             format!(
-                "({}/15/{})",
-                world_state.p2_score.touches, world_state.p2_score.matches
+                "({}/{}/{})",
+                bout.state.p2_score.touches,
+                bout.state.rules.touches_to_win,
+                bout.state.p2_score.matches
             ),
             TextStyle {
                 // This font is loaded and will be used instead of the default font.
@@ -246,6 +317,49 @@ fn score_board(mut commands: Commands, world_state: Res<GameState>) {
         }),
         ScoreBoard,
     ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            clock_text(&bout.state),
+            TextStyle {
+                font_size: 24.0,
+                ..default()
+            },
+        )
+        .with_text_justify(JustifyText::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Vw(12.5),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        }),
+        ClockText,
+        ScoreBoard,
+    ));
+}
+
+/// counts the clock down as the bout is fenced.
+fn show_clock(bout: Res<Bout>, mut clock_query: Query<&mut Text, With<ClockText>>) {
+    if let Ok(mut text) = clock_query.get_single_mut() {
+        text.sections[0].value = clock_text(&bout.state);
+    }
+}
+
+/// the period (or priority minute) being fenced, and the time left in it. empty without a
+/// clock.
+fn clock_text(state: &GameState) -> String {
+    if state.rules.period.is_none() {
+        return String::new();
+    }
+
+    let seconds = (state.clock as f32 * TICK).ceil() as u32;
+    let left = format!("{}:{:02}", seconds / 60, seconds % 60);
+
+    match state.priority {
+        Some(holder) => format!("Player {holder:?} has priority {left}"),
+        None => format!("Period {} {left}", state.period),
+    }
 }
 
 fn rm_score_board(mut commands: Commands, score_boards: Query<Entity, With<ScoreBoard>>) {
@@ -0,0 +1,647 @@
+use crate::paths::config_dir;
+use bevy::prelude::*;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// something the player does with a key or gamepad button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    Advance,
+    Retreat,
+    Lunge,
+    Fleche,
+    Balestra,
+    Beat,
+    Crouch,
+    Stance,
+    GaurdUp,
+    GaurdDown,
+    GaurdLeft,
+    GaurdRight,
+    Pause,
+}
+
+impl InputAction {
+    /// every action, in the order the controls screen lists them.
+    pub const ALL: [InputAction; 13] = [
+        Self::Advance,
+        Self::Retreat,
+        Self::Lunge,
+        Self::Fleche,
+        Self::Balestra,
+        Self::Beat,
+        Self::Crouch,
+        Self::Stance,
+        Self::GaurdUp,
+        Self::GaurdDown,
+        Self::GaurdLeft,
+        Self::GaurdRight,
+        Self::Pause,
+    ];
+
+    /// how the action is written in the controls file.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Advance => "advance",
+            Self::Retreat => "retreat",
+            Self::Lunge => "lunge",
+            Self::Fleche => "fleche",
+            Self::Balestra => "balestra",
+            Self::Beat => "beat",
+            Self::Crouch => "crouch",
+            Self::Stance => "stance",
+            Self::GaurdUp => "gaurd_up",
+            Self::GaurdDown => "gaurd_down",
+            Self::GaurdLeft => "gaurd_left",
+            Self::GaurdRight => "gaurd_right",
+            Self::Pause => "pause",
+        }
+    }
+
+    /// how the action is shown on the controls screen.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Advance => "Advance",
+            Self::Retreat => "Retreat",
+            Self::Lunge => "Lunge",
+            Self::Fleche => "Fleche",
+            Self::Balestra => "Balestra",
+            Self::Beat => "Beat",
+            Self::Crouch => "Crouch",
+            Self::Stance => "Switch Stance",
+            Self::GaurdUp => "Gaurd Up",
+            Self::GaurdDown => "Gaurd Down",
+            Self::GaurdLeft => "Gaurd Left",
+            Self::GaurdRight => "Gaurd Right",
+            Self::Pause => "Pause",
+        }
+    }
+
+    /// the way to push a gamepad stick that also does the action, if there is one. the left
+    /// stick moves, the right stick picks the gaurd.
+    pub fn stick(self) -> Option<(GamepadAxisType, f32)> {
+        match self {
+            Self::Advance => Some((GamepadAxisType::LeftStickX, 1.0)),
+            Self::Retreat => Some((GamepadAxisType::LeftStickX, -1.0)),
+            Self::Crouch => Some((GamepadAxisType::LeftStickY, -1.0)),
+            Self::GaurdUp => Some((GamepadAxisType::RightStickY, 1.0)),
+            Self::GaurdDown => Some((GamepadAxisType::RightStickY, -1.0)),
+            Self::GaurdLeft => Some((GamepadAxisType::RightStickX, -1.0)),
+            Self::GaurdRight => Some((GamepadAxisType::RightStickX, 1.0)),
+            Self::Lunge
+            | Self::Fleche
+            | Self::Balestra
+            | Self::Beat
+            | Self::Stance
+            | Self::Pause => None,
+        }
+    }
+}
+
+/// the keys an action can be bound to.
+pub const BINDABLE_KEYS: [KeyCode; 79] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Backquote,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+];
+
+/// the gamepad buttons an action can be bound to.
+pub const BINDABLE_BUTTONS: [GamepadButtonType; 18] = [
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::C,
+    GamepadButtonType::Z,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+/// how a key is written in the controls file, its name in bevy (ie. `KeyA` or `ArrowUp`).
+fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| key_name(*key) == name)
+}
+
+/// how a gamepad button is written in the controls file, its name in bevy (ie. `South`).
+fn button_name(button: GamepadButtonType) -> String {
+    format!("{button:?}")
+}
+
+fn parse_button(name: &str) -> Option<GamepadButtonType> {
+    BINDABLE_BUTTONS
+        .iter()
+        .copied()
+        .find(|button| button_name(*button) == name)
+}
+
+/// how a gamepad button is shown on screen, named like on an xbox controller.
+pub fn button_label(button: GamepadButtonType) -> String {
+    match button {
+        GamepadButtonType::South => "A".to_string(),
+        GamepadButtonType::East => "B".to_string(),
+        GamepadButtonType::North => "Y".to_string(),
+        GamepadButtonType::West => "X".to_string(),
+        GamepadButtonType::LeftTrigger => "LB".to_string(),
+        GamepadButtonType::LeftTrigger2 => "LT".to_string(),
+        GamepadButtonType::RightTrigger => "RB".to_string(),
+        GamepadButtonType::RightTrigger2 => "RT".to_string(),
+        GamepadButtonType::Select => "Back".to_string(),
+        GamepadButtonType::LeftThumb => "LS".to_string(),
+        GamepadButtonType::RightThumb => "RS".to_string(),
+        button => button_name(button).replace("DPad", "D-Pad "),
+    }
+}
+
+/// how a key is shown on screen.
+pub fn key_label(key: KeyCode) -> String {
+    let name = key_name(key);
+
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// the default for how quickly (in seconds) advance or retreat has to be pressed again to
+/// double it.
+const DOUBLE_TAP: f32 = 0.3;
+/// how the double tap window is written in the controls file.
+const DOUBLE_TAP_NAME: &str = "double_tap";
+/// what the second persons controls start with in the controls file, ie. `p2_lunge = KeyI`.
+const P2_PREFIX: &str = "p2_";
+
+/// the file the controls are saved to.
+pub fn controls_path() -> PathBuf {
+    config_dir().join("controls.cfg")
+}
+
+/// which key and gamepad button does what. every action has exactly one of each, and no two
+/// actions share a key or button. the two people at this machine don't share keys either, but
+/// each has their own gamepad.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Bindings {
+    keys: HashMap<InputAction, KeyCode>,
+    buttons: HashMap<InputAction, GamepadButtonType>,
+    /// how quickly (in seconds) advance or retreat has to be pressed again to double it.
+    double_tap: f32,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::from([
+                (InputAction::Advance, KeyCode::KeyD),
+                (InputAction::Retreat, KeyCode::KeyA),
+                (InputAction::Lunge, KeyCode::KeyW),
+                (InputAction::Fleche, KeyCode::KeyQ),
+                (InputAction::Balestra, KeyCode::KeyR),
+                (InputAction::Beat, KeyCode::KeyE),
+                (InputAction::Crouch, KeyCode::KeyS),
+                (InputAction::Stance, KeyCode::KeyF),
+                (InputAction::GaurdUp, KeyCode::ArrowUp),
+                (InputAction::GaurdDown, KeyCode::ArrowDown),
+                (InputAction::GaurdLeft, KeyCode::ArrowLeft),
+                (InputAction::GaurdRight, KeyCode::ArrowRight),
+                (InputAction::Pause, KeyCode::Escape),
+            ]),
+            buttons: HashMap::from([
+                (InputAction::Advance, GamepadButtonType::DPadRight),
+                (InputAction::Retreat, GamepadButtonType::DPadLeft),
+                (InputAction::Lunge, GamepadButtonType::RightTrigger2),
+                (InputAction::Fleche, GamepadButtonType::LeftTrigger2),
+                (InputAction::Balestra, GamepadButtonType::LeftTrigger),
+                (InputAction::Beat, GamepadButtonType::RightTrigger),
+                (InputAction::Crouch, GamepadButtonType::DPadDown),
+                (InputAction::Stance, GamepadButtonType::LeftThumb),
+                (InputAction::GaurdUp, GamepadButtonType::North),
+                (InputAction::GaurdDown, GamepadButtonType::South),
+                (InputAction::GaurdLeft, GamepadButtonType::West),
+                (InputAction::GaurdRight, GamepadButtonType::East),
+                (InputAction::Pause, GamepadButtonType::Start),
+            ]),
+            double_tap: DOUBLE_TAP,
+        }
+    }
+}
+
+/// the bindings of the second person fencing on this machine, the right hand side of the
+/// keyboard and the second gamepad plugged in.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PlayerTwoBindings(pub Bindings);
+
+impl Default for PlayerTwoBindings {
+    fn default() -> Self {
+        Self(Bindings {
+            keys: HashMap::from([
+                (InputAction::Advance, KeyCode::KeyL),
+                (InputAction::Retreat, KeyCode::KeyJ),
+                (InputAction::Lunge, KeyCode::KeyI),
+                (InputAction::Fleche, KeyCode::KeyU),
+                (InputAction::Balestra, KeyCode::KeyP),
+                (InputAction::Beat, KeyCode::KeyO),
+                (InputAction::Crouch, KeyCode::KeyK),
+                (InputAction::Stance, KeyCode::Semicolon),
+                (InputAction::GaurdUp, KeyCode::Numpad8),
+                (InputAction::GaurdDown, KeyCode::Numpad2),
+                (InputAction::GaurdLeft, KeyCode::Numpad4),
+                (InputAction::GaurdRight, KeyCode::Numpad6),
+                (InputAction::Pause, KeyCode::NumpadEnter),
+            ]),
+            ..Bindings::default()
+        })
+    }
+}
+
+impl Bindings {
+    pub fn key(&self, action: InputAction) -> KeyCode {
+        self.keys[&action]
+    }
+
+    pub fn button(&self, action: InputAction) -> GamepadButtonType {
+        self.buttons[&action]
+    }
+
+    /// wether any action is on `key`.
+    pub fn binds_key(&self, key: KeyCode) -> bool {
+        self.keys.values().any(|bound| *bound == key)
+    }
+
+    /// wether any action is on the gamepad `button`.
+    pub fn binds_button(&self, button: GamepadButtonType) -> bool {
+        self.buttons.values().any(|bound| *bound == button)
+    }
+
+    pub fn double_tap(&self) -> f32 {
+        self.double_tap
+    }
+
+    pub fn pressed(&self, keyboard_input: &ButtonInput<KeyCode>, action: InputAction) -> bool {
+        keyboard_input.pressed(self.key(action))
+    }
+
+    pub fn just_released(
+        &self,
+        keyboard_input: &ButtonInput<KeyCode>,
+        action: InputAction,
+    ) -> bool {
+        keyboard_input.just_released(self.key(action))
+    }
+
+    /// binds `key` to `action`. if another action already had that key, the two swap keys and
+    /// the other action is returned.
+    pub fn bind(&mut self, action: InputAction, key: KeyCode) -> Option<InputAction> {
+        let conflict = InputAction::ALL
+            .into_iter()
+            .find(|other| *other != action && self.key(*other) == key);
+        let old = self.keys.insert(action, key);
+
+        if let (Some(other), Some(old)) = (conflict, old) {
+            self.keys.insert(other, old);
+        }
+
+        conflict
+    }
+
+    /// binds `key` to `action` like `bind`, but also takes it off `other`, the other person at
+    /// this machine. if they had it, their action gets the old key of this one.
+    pub fn bind_beside(
+        &mut self,
+        other: &mut Bindings,
+        action: InputAction,
+        key: KeyCode,
+    ) -> Option<Swapped> {
+        let theirs = InputAction::ALL
+            .into_iter()
+            .find(|theirs| other.key(*theirs) == key);
+
+        match theirs {
+            Some(theirs) => {
+                let old = self.keys.insert(action, key);
+
+                if let Some(old) = old {
+                    other.keys.insert(theirs, old);
+                }
+
+                Some(Swapped::Other(theirs))
+            }
+            None => self.bind(action, key).map(Swapped::Own),
+        }
+    }
+
+    /// puts these bindings back to `defaults`, taking the keys off `other` like `bind_beside`.
+    pub fn reset_beside(&mut self, other: &mut Bindings, defaults: Bindings) {
+        for action in InputAction::ALL {
+            self.bind_beside(other, action, defaults.key(action));
+        }
+
+        self.buttons = defaults.buttons;
+        self.double_tap = defaults.double_tap;
+    }
+
+    /// binds the gamepad `button` to `action`, swapping with whatever action had it like `bind`.
+    pub fn bind_button(
+        &mut self,
+        action: InputAction,
+        button: GamepadButtonType,
+    ) -> Option<InputAction> {
+        let conflict = InputAction::ALL
+            .into_iter()
+            .find(|other| *other != action && self.button(*other) == button);
+        let old = self.buttons.insert(action, button);
+
+        if let (Some(other), Some(old)) = (conflict, old) {
+            self.buttons.insert(other, old);
+        }
+
+        conflict
+    }
+
+    /// changes the setting called `name` in the controls file to `value`. keys are taken off
+    /// `other` like `bind_beside`. None if either can't be read.
+    fn set(&mut self, other: &mut Bindings, name: &str, value: &str) -> Option<()> {
+        if name == DOUBLE_TAP_NAME {
+            self.double_tap = value
+                .parse()
+                .ok()
+                .filter(|seconds: &f32| (0.0..=1.0).contains(seconds))?;
+            return Some(());
+        }
+
+        InputAction::ALL.into_iter().find_map(|action| {
+            if name == action.name() {
+                self.bind_beside(other, action, parse_key(value)?);
+                Some(())
+            } else if name.strip_suffix("_pad") == Some(action.name()) {
+                self.bind_button(action, parse_button(value)?);
+                Some(())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// how these bindings are written in the controls file, every setting starting with
+    /// `prefix`.
+    fn write(&self, prefix: &str, text: &mut String) {
+        text.push_str(&format!(
+            "{prefix}{DOUBLE_TAP_NAME} = {}\n",
+            self.double_tap
+        ));
+
+        for action in InputAction::ALL {
+            text.push_str(&format!(
+                "{prefix}{} = {}\n",
+                action.name(),
+                key_name(self.key(action))
+            ));
+        }
+
+        for action in InputAction::ALL {
+            text.push_str(&format!(
+                "{prefix}{}_pad = {}\n",
+                action.name(),
+                button_name(self.button(action))
+            ));
+        }
+    }
+}
+
+/// an action that had a key before it was rebound. it gets the old key of the action that took
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Swapped {
+    /// one of the same persons actions.
+    Own(InputAction),
+    /// one of the other persons actions.
+    Other(InputAction),
+}
+
+/// reads both peoples controls from `path`, one `action = key` or `action_pad = button` per
+/// line, and the double tap window as `double_tap = seconds`. the second persons start with
+/// `p2_`. anything missing from the file keeps its default.
+pub fn load(path: &Path) -> io::Result<(Bindings, PlayerTwoBindings)> {
+    let mut p1 = Bindings::default();
+    let mut p2 = PlayerTwoBindings::default();
+
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let bound = line.split_once('=').and_then(|(name, value)| {
+            let (name, value) = (name.trim(), value.trim());
+
+            match name.strip_prefix(P2_PREFIX) {
+                Some(name) => p2.0.set(&mut p1, name, value),
+                None => p1.set(&mut p2.0, name, value),
+            }
+        });
+
+        if bound.is_none() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("line {} is not \"action = key\": {line}", i + 1),
+            ));
+        }
+    }
+
+    Ok((p1, p2))
+}
+
+pub fn save(path: &Path, p1: &Bindings, p2: &PlayerTwoBindings) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut text = String::from(
+        "# fencing-game controls, one \"action = key\" or \"action_pad = button\" per line\n",
+    );
+
+    p1.write("", &mut text);
+    text.push_str("# the second person fencing on this machine\n");
+    p2.0.write(P2_PREFIX, &mut text);
+
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempFile(PathBuf);
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// wether the two people at this machine have no key in common.
+    fn apart(p1: &Bindings, p2: &PlayerTwoBindings) -> bool {
+        InputAction::ALL.into_iter().all(|a| {
+            InputAction::ALL
+                .into_iter()
+                .all(|b| p1.key(a) != p2.0.key(b))
+        })
+    }
+
+    #[test]
+    fn defaults_share_no_keys() {
+        assert!(apart(&Bindings::default(), &PlayerTwoBindings::default()));
+    }
+
+    #[test]
+    fn skip_buttons_are_free_by_default() {
+        let (p1, p2) = (Bindings::default(), PlayerTwoBindings::default());
+
+        assert!(!p1.binds_key(KeyCode::Enter) && !p2.0.binds_key(KeyCode::Enter));
+        assert!(!p1.binds_button(GamepadButtonType::Select));
+        // A sets the low gaurd, so it can't skip.
+        assert!(p1.binds_button(GamepadButtonType::South));
+    }
+
+    #[test]
+    fn taking_the_other_persons_key_swaps_it() {
+        let mut p1 = Bindings::default();
+        let mut p2 = PlayerTwoBindings::default();
+
+        let swapped = p1.bind_beside(&mut p2.0, InputAction::Lunge, KeyCode::KeyJ);
+
+        assert_eq!(swapped, Some(Swapped::Other(InputAction::Retreat)));
+        assert_eq!(p1.key(InputAction::Lunge), KeyCode::KeyJ);
+        assert_eq!(p2.0.key(InputAction::Retreat), KeyCode::KeyW);
+        assert!(apart(&p1, &p2));
+
+        let swapped = p1.bind_beside(&mut p2.0, InputAction::Lunge, KeyCode::KeyD);
+        assert_eq!(swapped, Some(Swapped::Own(InputAction::Advance)));
+        assert!(apart(&p1, &p2));
+    }
+
+    #[test]
+    fn reset_takes_the_defaults_back() {
+        let mut p1 = Bindings::default();
+        let mut p2 = PlayerTwoBindings::default();
+        p2.0.bind_beside(&mut p1, InputAction::Advance, KeyCode::KeyD);
+        p1.bind_button(InputAction::Lunge, GamepadButtonType::South);
+
+        p1.reset_beside(&mut p2.0, Bindings::default());
+
+        assert_eq!(p1, Bindings::default());
+        assert!(apart(&p1, &p2));
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let file = TempFile(
+            std::env::temp_dir().join(format!("fencing-game-{}-controls.cfg", std::process::id())),
+        );
+        let mut p1 = Bindings::default();
+        let mut p2 = PlayerTwoBindings::default();
+        p1.bind_beside(&mut p2.0, InputAction::Beat, KeyCode::KeyO);
+        p2.0.bind_button(InputAction::Lunge, GamepadButtonType::South);
+        p2.0.double_tap = 0.5;
+
+        save(&file.0, &p1, &p2).unwrap();
+
+        assert_eq!(load(&file.0).unwrap(), (p1, p2));
+    }
+}
@@ -0,0 +1,159 @@
+use super::bindings::{Bindings, InputAction, PlayerTwoBindings};
+use bevy::{
+    ecs::system::SystemParam,
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+};
+
+/// how far (from 0 to 1) a stick has to be pushed before it counts.
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// the gamepads plugged in, in the order they were plugged in. the first one is player ones,
+/// the second is player twos when two people fence on this machine.
+#[derive(Resource, Debug, Default)]
+pub struct PadSlots(Vec<Gamepad>);
+
+impl PadSlots {
+    pub fn get(&self, slot: usize) -> Option<Gamepad> {
+        self.0.get(slot).copied()
+    }
+}
+
+/// keeps track of gamepads being plugged in and pulled out while the game runs.
+pub fn track_gamepads(
+    mut slots: ResMut<PadSlots>,
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+) {
+    for event in connection_events.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                if !slots.0.contains(&event.gamepad) {
+                    info!("gamepad {} ({}) plugged in", event.gamepad.id, info.name);
+                    slots.0.push(event.gamepad);
+                }
+            }
+            GamepadConnection::Disconnected => {
+                info!("gamepad {} pulled out", event.gamepad.id);
+                slots.0.retain(|pad| *pad != event.gamepad);
+            }
+        }
+    }
+}
+
+/// reads the actions of the people at this machine, from the keyboard and their gamepads. a
+/// seat is 0 for the first person (player one's bindings and gamepad), and 1 for the second.
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    pad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    pad_axes: Res<'w, Axis<GamepadAxis>>,
+    slots: Res<'w, PadSlots>,
+    bindings: Res<'w, Bindings>,
+    player_two: Res<'w, PlayerTwoBindings>,
+}
+
+impl ActionInput<'_> {
+    fn bindings(&self, seat: usize) -> &Bindings {
+        if seat == 0 {
+            &self.bindings
+        } else {
+            &self.player_two.0
+        }
+    }
+
+    /// wether the action's key, button, or stick direction is held down.
+    pub fn pressed(&self, seat: usize, action: InputAction) -> bool {
+        let bindings = self.bindings(seat);
+
+        if bindings.pressed(&self.keyboard_input, action) {
+            return true;
+        }
+
+        let Some(pad) = self.slots.get(seat) else {
+            return false;
+        };
+
+        let stick = action.stick().is_some_and(|(axis_type, direction)| {
+            self.pad_axes
+                .get(GamepadAxis::new(pad, axis_type))
+                .is_some_and(|value| value * direction >= STICK_THRESHOLD)
+        });
+
+        stick
+            || self
+                .pad_buttons
+                .pressed(GamepadButton::new(pad, bindings.button(action)))
+    }
+
+    /// how quickly (in seconds) advance or retreat has to be pressed again to double it.
+    pub fn double_tap(&self, seat: usize) -> f32 {
+        self.bindings(seat).double_tap()
+    }
+
+    pub fn just_released(&self, seat: usize, action: InputAction) -> bool {
+        let bindings = self.bindings(seat);
+
+        bindings.just_released(&self.keyboard_input, action)
+            || self.slots.get(seat).is_some_and(|pad| {
+                self.pad_buttons
+                    .just_released(GamepadButton::new(pad, bindings.button(action)))
+            })
+    }
+}
+
+/// the menu navigation done this frame, from the keyboard or any gamepad.
+#[derive(Resource, Debug, Default)]
+pub struct MenuInput {
+    /// down arrow, tab, or down on a d-pad.
+    pub next: bool,
+    /// up arrow, or up on a d-pad.
+    pub prev: bool,
+    /// enter, or A.
+    pub press: bool,
+    /// escape, or B.
+    pub back: bool,
+    /// enter, A, or back, but only if no fencing action is bound to it. ends a wait, like the
+    /// countdown, without a fencer setting their gaurd ending it by accident.
+    pub skip: bool,
+    /// wether any key or gamepad button is held down.
+    pub held: bool,
+}
+
+pub fn read_menu_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    bindings: Res<Bindings>,
+    player_two: Res<PlayerTwoBindings>,
+    mut menu_input: ResMut<MenuInput>,
+) {
+    let pad_released = |button_type: GamepadButtonType| {
+        pad_buttons
+            .get_just_released()
+            .any(|button| button.button_type == button_type)
+    };
+
+    *menu_input = MenuInput {
+        next: keyboard_input.just_released(KeyCode::ArrowDown)
+            || keyboard_input.just_released(KeyCode::Tab)
+            || pad_released(GamepadButtonType::DPadDown),
+        prev: keyboard_input.just_released(KeyCode::ArrowUp)
+            || pad_released(GamepadButtonType::DPadUp),
+        press: keyboard_input.just_released(KeyCode::Enter)
+            || pad_released(GamepadButtonType::South),
+        back: keyboard_input.just_released(KeyCode::Escape)
+            || pad_released(GamepadButtonType::East),
+        skip: [KeyCode::Enter].into_iter().any(|key| {
+            keyboard_input.just_released(key)
+                && !bindings.binds_key(key)
+                && !player_two.0.binds_key(key)
+        }) || [GamepadButtonType::South, GamepadButtonType::Select]
+            .into_iter()
+            .any(|button| {
+                pad_released(button)
+                    && !bindings.binds_button(button)
+                    && !player_two.0.binds_button(button)
+            }),
+        held: keyboard_input.get_pressed().next().is_some()
+            || pad_buttons.get_pressed().next().is_some(),
+    };
+}
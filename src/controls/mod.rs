@@ -0,0 +1,679 @@
+use crate::{despawn_buttons, fighter::Player, state::Screen, ButtonInteractions, ButtonMarker};
+use bevy::{input::InputSystem, prelude::*};
+use bindings::{
+    button_label, controls_path, key_label, Bindings, InputAction, PlayerTwoBindings, Swapped,
+    BINDABLE_BUTTONS, BINDABLE_KEYS,
+};
+use input::{read_menu_input, track_gamepads, MenuInput, PadSlots};
+use std::io::ErrorKind;
+
+pub mod bindings;
+pub mod input;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ControlsButton {
+    /// switches between the controls of the two people at this machine.
+    Player,
+    /// rebinds the action.
+    Bind(InputAction),
+    Defaults,
+    Back,
+}
+
+impl ControlsButton {
+    fn next(index: ControlsButton) -> Self {
+        match index {
+            Self::Player => Self::Bind(InputAction::ALL[0]),
+            Self::Bind(action) => {
+                let i = InputAction::ALL.iter().position(|a| *a == action);
+
+                match i.and_then(|i| InputAction::ALL.get(i + 1)) {
+                    Some(next) => Self::Bind(*next),
+                    None => Self::Defaults,
+                }
+            }
+            Self::Defaults => Self::Back,
+            Self::Back => Self::Player,
+        }
+    }
+
+    fn prev(index: ControlsButton) -> Self {
+        match index {
+            Self::Player => Self::Back,
+            Self::Bind(action) => {
+                let i = InputAction::ALL.iter().position(|a| *a == action);
+
+                match i.and_then(|i| i.checked_sub(1)) {
+                    Some(i) => Self::Bind(InputAction::ALL[i]),
+                    None => Self::Player,
+                }
+            }
+            Self::Defaults => Self::Bind(InputAction::ALL[InputAction::ALL.len() - 1]),
+            Self::Back => Self::Defaults,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SelectedButton(Option<ControlsButton>, Interaction);
+
+/// what the controls screen is in the middle of.
+#[derive(Resource)]
+struct ControlsMenu {
+    /// whose controls are being changed.
+    player: Player,
+    /// the action waiting for the player to press its new key.
+    waiting: Option<InputAction>,
+    /// set when a key or button was just bound. letting go of it should not also work the menu,
+    /// so the menu waits for everything to be let go.
+    swallow: bool,
+    /// where the back button goes, the controls can be opened from the welcome or pause menu.
+    back_to: Screen,
+    status: String,
+}
+
+#[derive(Component)]
+struct StatusText;
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+
+/// shows the controls and lets the player rebind them
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bindings>()
+            .init_resource::<PlayerTwoBindings>()
+            .init_resource::<PadSlots>()
+            .init_resource::<MenuInput>()
+            .insert_resource(ControlsMenu {
+                player: Player::One,
+                waiting: None,
+                swallow: false,
+                back_to: Screen::Welcome,
+                status: String::new(),
+            })
+            .add_systems(Startup, load_bindings)
+            .add_systems(
+                PreUpdate,
+                (track_gamepads, read_menu_input).after(InputSystem),
+            )
+            .add_systems(
+                OnTransition {
+                    from: Screen::Welcome,
+                    to: Screen::Controls,
+                },
+                back_to_welcome,
+            )
+            .add_systems(
+                OnTransition {
+                    from: Screen::PauseMenu,
+                    to: Screen::Controls,
+                },
+                back_to_pause_menu,
+            )
+            .add_systems(OnEnter(Screen::Controls), spawn_buttons)
+            .add_systems(OnExit(Screen::Controls), despawn_buttons)
+            .add_systems(
+                Update,
+                (
+                    keyboard_select,
+                    mouse_select,
+                    button_selection,
+                    rebind,
+                    show_bindings,
+                )
+                    .chain()
+                    .run_if(in_state(Screen::Controls)),
+            );
+    }
+}
+
+fn load_bindings(mut commands: Commands) {
+    let path = controls_path();
+
+    match bindings::load(&path) {
+        Ok((p1, p2)) => {
+            info!("loaded the controls from {}", path.display());
+            commands.insert_resource(p1);
+            commands.insert_resource(p2);
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            info!(
+                "no controls found at {}, using the defaults",
+                path.display()
+            );
+        }
+        Err(e) => error!(
+            "could not read the controls at {}, using the defaults: {e}",
+            path.display()
+        ),
+    }
+}
+
+fn back_to_welcome(mut menu: ResMut<ControlsMenu>) {
+    menu.back_to = Screen::Welcome;
+}
+
+fn back_to_pause_menu(mut menu: ResMut<ControlsMenu>) {
+    menu.back_to = Screen::PauseMenu;
+}
+
+/// spawns the buttons (and button text) of the controls menu
+fn spawn_buttons(mut commands: Commands, p1: Res<Bindings>, mut menu: ResMut<ControlsMenu>) {
+    debug!("spawning controls menu buttons");
+
+    menu.player = Player::One;
+    menu.waiting = None;
+    menu.swallow = false;
+    menu.status = "Pick an action to change its key".to_string();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ButtonMarker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 32.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        })
+        // status line
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    menu.status.clone(),
+                    TextStyle {
+                        // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 12.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                StatusText,
+            ));
+        })
+        // whose controls are shown
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(20.0),
+                        height: Val::Vh(6.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            player_label(Player::One),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 12.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ControlsButton::Player,
+                    ));
+                });
+        })
+        // a button per action, two to a row
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Vw(80.0),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        column_gap: Val::Px(4.0),
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for action in InputAction::ALL {
+                        parent
+                            .spawn(ButtonBundle {
+                                style: Style {
+                                    width: Val::Vw(38.0),
+                                    height: Val::Vh(6.0),
+                                    border: UiRect::all(Val::Px(1.0)),
+                                    // horizontally center child text
+                                    justify_content: JustifyContent::Center,
+                                    // vertically center child text
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                border_color: BorderColor(Color::BLACK),
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        binding_label(action, &p1, &menu),
+                                        TextStyle {
+                                            // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                            font_size: 12.0,
+                                            color: Color::rgb(0.9, 0.9, 0.9),
+                                            ..default()
+                                        },
+                                    ),
+                                    ControlsButton::Bind(action),
+                                ));
+                            });
+                    }
+                });
+        })
+        // Defaults button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(20.0),
+                        height: Val::Vh(6.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Reset to Defaults",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 12.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ControlsButton::Defaults,
+                    ));
+                });
+        })
+        // Back button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(6.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Back",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 12.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ControlsButton::Back,
+                    ));
+                });
+        });
+
+    commands.insert_resource(SelectedButton(None, Interaction::None));
+}
+
+/// the controls of whoever is being changed.
+fn editing<'a>(menu: &ControlsMenu, p1: &'a Bindings, p2: &'a PlayerTwoBindings) -> &'a Bindings {
+    match menu.player {
+        Player::One => p1,
+        Player::Two => &p2.0,
+    }
+}
+
+/// how a person at this machine is named on the controls screen.
+fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::One => "Player 1",
+        Player::Two => "Player 2",
+    }
+}
+
+/// the text of the button that switches whose controls are shown.
+fn player_label(player: Player) -> String {
+    format!("{} Controls", player_name(player))
+}
+
+fn binding_label(action: InputAction, bindings: &Bindings, menu: &ControlsMenu) -> String {
+    if menu.waiting == Some(action) {
+        format!("{}: press a key or button...", action.label())
+    } else {
+        format!(
+            "{}: {} / {}",
+            action.label(),
+            key_label(bindings.key(action)),
+            button_label(bindings.button(action))
+        )
+    }
+}
+
+/// handles using the keyboard to select a button
+fn keyboard_select(
+    menu_input: Res<MenuInput>,
+    mut button_selection: ResMut<SelectedButton>,
+    mut menu: ResMut<ControlsMenu>,
+    mut next_state: ResMut<NextState<Screen>>,
+) {
+    if menu.waiting.is_some() {
+        return;
+    }
+
+    if menu.swallow {
+        if !menu_input.held {
+            menu.swallow = false;
+        }
+
+        return;
+    }
+
+    if menu_input.back {
+        next_state.set(menu.back_to);
+        return;
+    }
+
+    match button_selection.0 {
+        Some(button) => {
+            if menu_input.next {
+                debug!("changing button selection to next button with keeb");
+                button_selection.0 = Some(ControlsButton::next(button));
+            } else if menu_input.prev {
+                debug!("changing button selection to previous button with keeb");
+                button_selection.0 = Some(ControlsButton::prev(button));
+            } else if menu_input.press {
+                debug!("pressing button with keeb");
+                button_selection.1 = Interaction::Pressed;
+            }
+        }
+        None => {
+            if menu_input.prev || menu_input.next {
+                debug!("selecting first binding with keeb");
+                button_selection.0 = Some(ControlsButton::Bind(InputAction::ALL[0]));
+                button_selection.1 = Interaction::Hovered;
+            }
+        }
+    }
+}
+
+/// handles using the mouse to select a button
+fn mouse_select(
+    mut interaction_query: ButtonInteractions,
+    mut text_query: Query<&ControlsButton, With<Text>>,
+    mut button_selection: ResMut<SelectedButton>,
+) {
+    for (interaction, children) in &mut interaction_query {
+        let Ok(button_type) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        match *interaction {
+            Interaction::Pressed | Interaction::Hovered => {
+                debug!("selecting with mouse");
+                *button_selection = SelectedButton(Some(*button_type), *interaction);
+            }
+            Interaction::None => {
+                debug!("unselecting with mouse");
+                *button_selection = SelectedButton(None, *interaction);
+            }
+        }
+    }
+}
+
+/// handles changing the selected buttons collor and text
+fn button_selection(
+    mut button_query: Query<(&mut BackgroundColor, &mut BorderColor, &Children), With<Button>>,
+    mut text_query: Query<(&mut Text, &ControlsButton)>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut button_selection: ResMut<SelectedButton>,
+    mut menu: ResMut<ControlsMenu>,
+    mut p1: ResMut<Bindings>,
+    mut p2: ResMut<PlayerTwoBindings>,
+) {
+    let select_prefix = "> ";
+
+    for (mut color, mut border_color, children) in &mut button_query {
+        let Ok((mut text, button_type)) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        if Some(*button_type) == button_selection.0 {
+            match button_selection.1 {
+                Interaction::Pressed => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = PRESSED_BUTTON.into();
+
+                    if let Some(screen) = press_button(*button_type, &mut menu, &mut p1, &mut p2) {
+                        next_state.set(screen);
+                    }
+
+                    button_selection.1 = Interaction::Hovered;
+                }
+                Interaction::Hovered => {
+                    if !text.sections[0].value.starts_with(select_prefix) {
+                        text.sections[0].value =
+                            format!("{select_prefix}{}", text.sections[0].value);
+                    }
+
+                    *color = HOVERED_BUTTON.into();
+                    border_color.0 = Color::WHITE;
+                }
+                Interaction::None => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = NORMAL_BUTTON.into();
+                }
+            }
+        } else {
+            text.sections[0].value = text.sections[0]
+                .value
+                .strip_prefix(select_prefix)
+                .unwrap_or(text.sections[0].value.as_str())
+                .to_string();
+            *color = NORMAL_BUTTON.into();
+            border_color.0 = Color::BLACK;
+        }
+    }
+}
+
+/// called by mouse select or keyboard select. used to envoke the buttons function. returns the
+/// screen to go to, if the button leaves this one.
+fn press_button(
+    button: ControlsButton,
+    menu: &mut ControlsMenu,
+    p1: &mut Bindings,
+    p2: &mut PlayerTwoBindings,
+) -> Option<Screen> {
+    match button {
+        ControlsButton::Player => {
+            menu.player = menu.player.other();
+            menu.waiting = None;
+            menu.status = "Pick an action to change its key".to_string();
+            None
+        }
+        ControlsButton::Bind(action) => {
+            menu.waiting = Some(action);
+            menu.status = format!("Press the new key or button for {}", action.label());
+            None
+        }
+        ControlsButton::Defaults => {
+            match menu.player {
+                Player::One => p1.reset_beside(&mut p2.0, Bindings::default()),
+                Player::Two => p2.0.reset_beside(p1, PlayerTwoBindings::default().0),
+            }
+
+            menu.waiting = None;
+            menu.status = save(p1, p2, "Reset the controls to the defaults");
+            None
+        }
+        ControlsButton::Back => {
+            menu.waiting = None;
+            Some(menu.back_to)
+        }
+    }
+}
+
+/// binds the next key or gamepad button pressed to the action waiting for one.
+fn rebind(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut p1: ResMut<Bindings>,
+    mut p2: ResMut<PlayerTwoBindings>,
+) {
+    let Some(action) = menu.waiting else {
+        return;
+    };
+
+    let key = BINDABLE_KEYS
+        .into_iter()
+        .find(|key| keyboard_input.just_pressed(*key));
+    let button = pad_buttons
+        .get_just_pressed()
+        .map(|button| button.button_type)
+        .find(|button| BINDABLE_BUTTONS.contains(button));
+
+    let (bindings, other) = match menu.player {
+        Player::One => (&mut *p1, &mut p2.0),
+        Player::Two => (&mut p2.0, &mut *p1),
+    };
+
+    let done = if let Some(key) = key {
+        match bindings.bind_beside(other, action, key) {
+            Some(Swapped::Own(other)) => format!(
+                "{} was on {}, the two swapped keys",
+                key_label(key),
+                other.label()
+            ),
+            Some(Swapped::Other(other)) => format!(
+                "{} was on {} for {}, the two swapped keys",
+                key_label(key),
+                other.label(),
+                player_name(menu.player.other())
+            ),
+            None => format!("{} is now on {}", action.label(), key_label(key)),
+        }
+    } else if let Some(button) = button {
+        match bindings.bind_button(action, button) {
+            Some(other) => format!(
+                "{} was on {}, the two swapped buttons",
+                button_label(button),
+                other.label()
+            ),
+            None => format!("{} is now on {}", action.label(), button_label(button)),
+        }
+    } else {
+        return;
+    };
+
+    menu.waiting = None;
+    menu.swallow = true;
+    menu.status = save(&p1, &p2, &done);
+}
+
+/// writes both peoples controls to disk. returns the status line to show.
+fn save(p1: &Bindings, p2: &PlayerTwoBindings, done: &str) -> String {
+    let path = controls_path();
+
+    match bindings::save(&path, p1, p2) {
+        Ok(()) => {
+            info!("saved the controls to {}", path.display());
+            done.to_string()
+        }
+        Err(e) => {
+            error!("could not save the controls to {}: {e}", path.display());
+            format!("{done}, but could not save them: {e}")
+        }
+    }
+}
+
+/// keeps the button labels and status line up to date.
+fn show_bindings(
+    p1: Res<Bindings>,
+    p2: Res<PlayerTwoBindings>,
+    menu: Res<ControlsMenu>,
+    mut text_query: Query<(&mut Text, &ControlsButton)>,
+    mut status_query: Query<&mut Text, (With<StatusText>, Without<ControlsButton>)>,
+) {
+    if !p1.is_changed() && !p2.is_changed() && !menu.is_changed() {
+        return;
+    }
+
+    let select_prefix = "> ";
+    let bindings = editing(&menu, &p1, &p2);
+
+    for (mut text, button_type) in &mut text_query {
+        let label = match *button_type {
+            ControlsButton::Player => player_label(menu.player),
+            ControlsButton::Bind(action) => binding_label(action, bindings, &menu),
+            ControlsButton::Defaults | ControlsButton::Back => continue,
+        };
+
+        let prefix = if text.sections[0].value.starts_with(select_prefix) {
+            select_prefix
+        } else {
+            ""
+        };
+
+        text.sections[0].value = format!("{prefix}{label}");
+    }
+
+    if let Ok(mut status_text) = status_query.get_single_mut() {
+        status_text.sections[0].value = menu.status.clone();
+    }
+}
@@ -1,7 +1,8 @@
 use crate::PLAYER_SPEED;
 use bevy::prelude::*;
+use std::ops::Not;
 
-#[derive(Component)]
+#[derive(Debug, Clone)]
 pub struct Fighter {
     /// what gaurd is this player in.
     pub gaurd: Gaurd,
@@ -11,12 +12,15 @@ pub struct Fighter {
     pub position: f32,
     /// represents the players current stance.
     pub stance: Stance,
+    /// the stance the player picked, Offence or Defence. they go back to it once a lunge is
+    /// done.
+    pub en_garde: Stance,
     /// left or right handed?
     pub handed: Handed,
     /// is this player 1, 2.
     pub player: Player,
-    // /// who/what controls this figter.
-    // pub contoller: Controller,
+    /// who/what controls this figter.
+    pub controller: Controller,
     /// is this fighter mounting a parry.
     pub parrying: bool,
     /// the move that the player is doing. will block other movements untill done.
@@ -26,14 +30,34 @@ pub struct Fighter {
 }
 
 impl Fighter {
-    /// takes the time struct from the game, returns an amount to move the players sprite.
-    pub fn update_movement(&mut self, time: Time) -> f32 {
+    /// a fighter standing en garde on their side of the piste.
+    pub fn new(player: Player) -> Self {
+        Self {
+            gaurd: Gaurd::Left,
+            position: -2.0 * player.facing(),
+            stance: Stance::Offence,
+            en_garde: Stance::Offence,
+            handed: Handed::Right,
+            player,
+            controller: match player {
+                Player::One => Controller::Player,
+                Player::Two => Controller::Computer,
+            },
+            parrying: false,
+            action: Action::from(Move::EnGarde),
+            crouched: false,
+        }
+    }
+
+    /// takes the seconds passed since the last update, returns an amount to move the fighter.
+    pub fn update_movement(&mut self, time_d: f32) -> f32 {
         let b1 = self.action.blocked();
-        let res = self.action.step(time.delta_seconds());
+        let res = self.action.step(time_d);
         let b2 = self.action.blocked();
 
         if b1 && !b2 {
             self.set_action(Move::EnGarde);
+            self.stance = self.en_garde;
         }
 
         res
@@ -46,15 +70,87 @@ impl Fighter {
         }
     }
 
-    pub fn lunged(&self) -> bool {
-        self.action.act == Move::Lunge
+    /// switches to the offensive or defensive stance. a fighter can't pick to be lunged.
+    pub fn set_stance(&mut self, stance: Stance) {
+        if stance != Stance::Lunged {
+            self.en_garde = stance;
+            self.stance = stance;
+        }
+    }
+
+    /// turns the advance or retreat being taken into the double version of `act`, keeping the
+    /// time already spent on it. returns wether it did.
+    pub fn double_step(&mut self, act: Move) -> bool {
+        let Some(single) = act.single() else {
+            return false;
+        };
+
+        if self.action.act != single {
+            return false;
+        }
+
+        let spent = Action::from(single).block_for - self.action.block_for;
+        let mut action = Action::from(act);
+        action.block_for -= spent;
+        action.moved -= spent;
+        self.action = action;
+
+        true
+    }
+
+    /// wether this fighter is in the middle of an attack (a lunge, fleche, balestra, or
+    /// riposte).
+    pub fn attacking(&self) -> bool {
+        self.action.act.attack()
+    }
+
+    /// wether the fighters sword arm is all the way out. a riposte only lands once it is.
+    pub fn extended(&self) -> bool {
+        self.action.moved < 0.0
+    }
+
+    pub fn beating(&self) -> bool {
+        self.action.act == Move::Beat
+    }
+
+    /// the line this fighters attacks come in on. a crouched fighter can only attack low.
+    pub fn line(&self) -> Gaurd {
+        if self.crouched {
+            Gaurd::Down
+        } else {
+            self.gaurd
+        }
+    }
+
+    /// wether this fighters gaurd meets the blade of `attacker`.
+    pub fn parries(&self, attacker: &Fighter) -> bool {
+        self.gaurd
+            .parries(self.handed, attacker.line(), attacker.handed)
+    }
+
+    /// the gaurd this fighter needs to meet the blade of `attacker`.
+    pub fn gaurd_against(&self, attacker: &Fighter) -> Gaurd {
+        attacker
+            .line()
+            .for_hand(attacker.handed)
+            .for_hand(self.handed)
+    }
+
+    /// wether an attack from this fighter can land on `target`. a crouched fighter ducks
+    /// everything but low line attacks.
+    pub fn reaches(&self, target: &Fighter) -> bool {
+        !target.crouched || self.line() == Gaurd::Down
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Controller {
+    /// someone at this machine, with a keyboard or gamepad.
     Player,
+    /// one of the computer players.
     Computer,
+    /// someone at another machine on the LAN.
+    Remote,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -66,12 +162,31 @@ pub enum Gaurd {
 }
 
 impl Gaurd {
-    pub fn parries(&self, other: Self) -> bool {
-        // match (self, other) {
-        //     (Self::Up, Self::Up) => true,
-        //     _ => false,
-        // }
-        *self == other
+    /// wether this gaurd, held in the `handed` hand, meets `other`, held in the `other_handed`
+    /// hand.
+    pub fn parries(&self, handed: Handed, other: Self, other_handed: Handed) -> bool {
+        self.for_hand(handed) == other.for_hand(other_handed)
+    }
+
+    /// turns a gaurd held in the `handed` hand into the same line for the other hand, or back.
+    /// a lefties left gaurd covers the line a righties right gaurd does.
+    pub fn for_hand(&self, handed: Handed) -> Self {
+        match (handed, self) {
+            (Handed::Right, _) => *self,
+            (Handed::Left, Self::Left) => Self::Right,
+            (Handed::Left, Self::Right) => Self::Left,
+            (Handed::Left, _) => *self,
+        }
+    }
+
+    /// the gaurd on the other side, where a parried blade is knocked to.
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Right => Self::Left,
+            Self::Left => Self::Right,
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+        }
     }
 }
 
@@ -86,24 +201,36 @@ impl Into<usize> for Gaurd {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Stance {
-    /// swordhand in fort.
+    /// swordhand in fort. lunges reach further and are recovered from quicker.
     Offence,
-    /// swordhand in back.
+    /// swordhand in back. leaves more time to parry, and retreats quicker.
     Defence,
-    /// lunged forward from an offensive stance.
+    /// lunged forward, for as long as a lunge or balestra lasts.
     Lunged,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Handed {
     /// fighter is a righty.
+    #[default]
     Right,
     /// fighter is a lefty.
     Left,
 }
 
+impl Not for Handed {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Right => Self::Left,
+            Self::Left => Self::Right,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Player {
     /// player one.
@@ -112,13 +239,65 @@ pub enum Player {
     Two,
 }
 
+impl Player {
+    pub fn other(&self) -> Self {
+        match self {
+            Self::One => Self::Two,
+            Self::Two => Self::One,
+        }
+    }
+
+    /// the direction this players fighter moves when advancing. player one faces toward
+    /// positive positions, player two toward negative ones.
+    pub fn facing(&self) -> f32 {
+        match self {
+            Self::One => 1.0,
+            Self::Two => -1.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Move {
     Advance,
     Retreat,
     Lunge,
+    /// knocks the opponents blade aside to steal right of way.
+    Beat,
     EnGarde,
-    // TODO: add DoubleAdvance and DoubleRetreat
+    /// two quick advances, covers about twice the ground.
+    DoubleAdvance,
+    /// two quick retreats.
+    DoubleRetreat,
+    /// a running attack that carries the fighter passed the opponent.
+    Fleche,
+    /// a jump forward straight into a lunge. reaches further, but takes longer to recover from.
+    Balestra,
+    /// a quick thrust, with no lunge, right after a parry.
+    Riposte,
+}
+
+impl Move {
+    /// the single step a double step is made of.
+    pub fn single(self) -> Option<Self> {
+        match self {
+            Self::DoubleAdvance => Some(Self::Advance),
+            Self::DoubleRetreat => Some(Self::Retreat),
+            _ => None,
+        }
+    }
+
+    /// wether this is one of the attacks the fighter lunges for.
+    pub fn lunge(self) -> bool {
+        matches!(self, Self::Lunge | Self::Balestra)
+    }
+
+    pub fn attack(self) -> bool {
+        matches!(
+            self,
+            Self::Lunge | Self::Fleche | Self::Balestra | Self::Riposte
+        )
+    }
 }
 
 impl Into<((f32, f32), Vec3)> for Move {
@@ -127,6 +306,14 @@ impl Into<((f32, f32), Vec3)> for Move {
             Self::Advance => ((0.5, 0.35), Vec3::new(0.75, 0.0, 0.0)),
             Self::Retreat => ((0.5, 0.35), Vec3::new(-0.75, 0.0, 0.0)),
             Self::Lunge => ((0.75, 0.2), Vec3::new(0.75, 0.0, 0.0)),
+            // a quick knock of the blade, then a moment to recover.
+            Self::Beat => ((0.25, 0.0), Vec3::ZERO),
+            Self::DoubleAdvance => ((0.75, 0.6), Vec3::new(0.75, 0.0, 0.0)),
+            Self::DoubleRetreat => ((0.75, 0.6), Vec3::new(-0.75, 0.0, 0.0)),
+            Self::Fleche => ((1.0, 0.6), Vec3::new(0.75, 0.0, 0.0)),
+            Self::Balestra => ((1.1, 0.35), Vec3::new(0.75, 0.0, 0.0)),
+            // the arm takes a moment to extend, which is the time there is to parry it.
+            Self::Riposte => ((0.35, 0.15), Vec3::ZERO),
             // f32::NEG_INFINITY makes this non-blocking
             Self::EnGarde => ((f32::NEG_INFINITY, 0.0), Vec3::ZERO),
         }
@@ -139,12 +326,18 @@ impl From<Move> for f32 {
             Move::Advance => 1.0,
             Move::Retreat => 1.0,
             Move::Lunge => 4.0,
+            Move::Beat => 0.0,
             Move::EnGarde => 0.0,
+            Move::DoubleAdvance => 1.25,
+            Move::DoubleRetreat => 1.25,
+            Move::Fleche => 4.0,
+            Move::Balestra => 4.0,
+            Move::Riposte => 0.0,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Action {
     pub act: Move,
     /// remaining seconds to block for.
@@ -172,6 +365,11 @@ impl Action {
         self.block_for >= 0.0
     }
 
+    /// how long (in seconds) the action has been going for.
+    pub fn elapsed(&self) -> f32 {
+        Action::from(self.act).moved - self.moved
+    }
+
     fn step(&mut self, time_d: f32) -> f32 {
         self.block_for -= time_d;
         let res = if self.moved >= 0.0 {
@@ -0,0 +1,209 @@
+use super::{protocol::Message, receive, LanSession, Role};
+use crate::state::MatchRules;
+use bevy::prelude::*;
+use std::{
+    env, fs,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    time::{Duration, Instant},
+};
+
+/// the port the lobby listens for hosts announcing themselves on.
+pub const DISCOVERY_PORT: u16 = 7879;
+/// hosts also announce to this (site local) multicast group, which reaches other programs on the
+/// same machine even where broadcasts do not.
+const MULTICAST_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 70, 71);
+/// how often (in seconds) a host announces itself.
+const ANNOUNCE_INTERVAL: f32 = 1.0;
+/// how often (in seconds) the lobby measures the ping to each host.
+const PING_INTERVAL: f32 = 1.0;
+/// how long (in seconds) a host can go without announcing before the lobby forgets it.
+const FORGET_AFTER: f32 = 3.0;
+
+/// the name this machine goes by in other players lobbies.
+pub fn host_name() -> String {
+    env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| env::var("USER").or_else(|_| env::var("USERNAME")).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "fencer".to_string())
+}
+
+/// lets the LAN know about a host, so others can join or watch. the announcement goes out from
+/// the hosts own socket, so whoever hears it can reach the host at the address it came from.
+pub fn announce(
+    session: Res<LanSession>,
+    rules: Res<MatchRules>,
+    time: Res<Time>,
+    mut since: Local<f32>,
+) {
+    if session.role != Role::Host {
+        return;
+    }
+
+    *since -= time.delta_seconds();
+
+    if *since > 0.0 {
+        return;
+    }
+
+    *since = ANNOUNCE_INTERVAL;
+
+    let message = Message::Announce {
+        id: session.id,
+        name: session.name.clone(),
+        touches_to_win: rules.touches_to_win,
+        fencers: if session.connected { 2 } else { 1 },
+        spectators: session.spectators.len() as u8,
+    }
+    .encode();
+
+    for to in [
+        Ipv4Addr::BROADCAST,
+        MULTICAST_GROUP,
+        // reaches a lobby on the same machine, even without a network.
+        Ipv4Addr::LOCALHOST,
+    ] {
+        if let Err(e) = session
+            .socket
+            .send_to(&message, SocketAddrV4::new(to, DISCOVERY_PORT))
+        {
+            debug!("could not announce to {to}: {e}");
+        }
+    }
+}
+
+/// a host heard from on the LAN.
+#[derive(Debug, Clone)]
+pub struct LanGame {
+    /// the hosts session, the same host can be heard from more than one address.
+    id: u64,
+    pub name: String,
+    /// where to join the host.
+    pub addr: SocketAddr,
+    pub touches_to_win: u8,
+    /// 1 while the host waits for an opponent, 2 once the bout is under way.
+    pub fencers: u8,
+    pub spectators: u8,
+    /// the round trip time to the host, once it has been measured.
+    pub ping: Option<Duration>,
+    /// the last ping sent, and when.
+    ping_sent: Option<(u64, Instant)>,
+    /// seconds since the host last announced itself.
+    silence: f32,
+}
+
+/// listens for hosts announcing themselves, and keeps track of the ones that still are.
+#[derive(Resource)]
+pub struct Discovery {
+    socket: UdpSocket,
+    pub games: Vec<LanGame>,
+    /// seconds untill the hosts are next pinged.
+    next_ping: f32,
+    pings_sent: u64,
+}
+
+impl Discovery {
+    pub fn listen() -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], DISCOVERY_PORT)))?;
+        socket.set_nonblocking(true)?;
+
+        if let Err(e) = socket.join_multicast_v4(&MULTICAST_GROUP, &Ipv4Addr::UNSPECIFIED) {
+            // broadcasts (and hosts on this machine) will still be heard.
+            warn!("could not join the discovery multicast group: {e}");
+        }
+
+        Ok(Self {
+            socket,
+            games: Vec::new(),
+            next_ping: 0.0,
+            pings_sent: 0,
+        })
+    }
+
+    /// reads announcements and pongs, forgets quiet hosts and pings the rest. returns wether a
+    /// host was found or forgotten.
+    pub fn update(&mut self, time_d: f32) -> bool {
+        let mut changed = false;
+
+        for (message, from) in receive(&self.socket) {
+            match message {
+                Message::Announce {
+                    id,
+                    name,
+                    touches_to_win,
+                    fencers,
+                    spectators,
+                } => {
+                    if let Some(game) = self.games.iter_mut().find(|game| game.id == id) {
+                        game.name = name;
+                        game.touches_to_win = touches_to_win;
+                        game.fencers = fencers;
+                        game.spectators = spectators;
+                        game.silence = 0.0;
+                    } else {
+                        info!("found a LAN bout hosted by {name} at {from}");
+                        self.games.push(LanGame {
+                            id,
+                            name,
+                            addr: from,
+                            touches_to_win,
+                            fencers,
+                            spectators,
+                            ping: None,
+                            ping_sent: None,
+                            silence: 0.0,
+                        });
+                        changed = true;
+                    }
+                }
+                Message::Pong(n) => {
+                    if let Some(game) = self.games.iter_mut().find(|game| game.addr == from)
+                        && let Some((sent, at)) = game.ping_sent
+                        && sent == n
+                    {
+                        game.ping = Some(at.elapsed());
+                        game.ping_sent = None;
+                    }
+                }
+                message => debug!("ignoring {message:?} from {from} while discovering"),
+            }
+        }
+
+        let before = self.games.len();
+
+        for game in self.games.iter_mut() {
+            game.silence += time_d;
+        }
+
+        self.games.retain(|game| game.silence < FORGET_AFTER);
+        changed |= self.games.len() != before;
+
+        self.next_ping -= time_d;
+
+        if self.next_ping <= 0.0 {
+            self.next_ping = PING_INTERVAL;
+            self.ping();
+        }
+
+        changed
+    }
+
+    fn ping(&mut self) {
+        for game in self.games.iter_mut() {
+            self.pings_sent += 1;
+
+            if let Err(e) = self
+                .socket
+                .send_to(&Message::Ping(self.pings_sent).encode(), game.addr)
+            {
+                debug!("could not ping {}: {e}", game.addr);
+                continue;
+            }
+
+            game.ping_sent = Some((self.pings_sent, Instant::now()));
+        }
+    }
+}
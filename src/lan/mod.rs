@@ -0,0 +1,962 @@
+use crate::{
+    bout::{Bout, BoutEvent, TickInputs, TICK},
+    combat::{next_screen, reset_world, BoutInputs},
+    controls::input::MenuInput,
+    despawn_buttons,
+    fighter::Player,
+    player::{player_blade_play, player_movement},
+    rng::Rng,
+    state::{GameMode, Handedness, MatchRules, Screen},
+    ButtonInteractions, ButtonMarker, Pressed,
+};
+use bevy::{prelude::*, window::ReceivedCharacter};
+use protocol::{Message, Snapshot, MAX_PACKET};
+use rollback::{Rollback, DEFAULT_INPUT_DELAY, MAX_INPUT_DELAY};
+use spectator::SpectatorSession;
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+
+pub mod discovery;
+pub mod protocol;
+pub mod rollback;
+pub mod spectator;
+
+/// the port hosts listen on.
+pub const DEFAULT_PORT: u16 = 7878;
+/// how long (in seconds) to wait to hear from the other machine before giving up on it.
+const TIMEOUT: f32 = 5.0;
+/// how often (in seconds) a joining client asks the host to let it in.
+const HELLO_INTERVAL: f32 = 0.5;
+/// the most spectators a host lets watch.
+const MAX_SPECTATORS: usize = 8;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum LanMenuButton {
+    Host,
+    Join,
+    Watch,
+    Delay,
+    Back,
+}
+
+impl LanMenuButton {
+    fn next(index: LanMenuButton) -> Self {
+        match index {
+            Self::Host => Self::Join,
+            Self::Join => Self::Watch,
+            Self::Watch => Self::Delay,
+            Self::Delay => Self::Back,
+            Self::Back => Self::Host,
+        }
+    }
+
+    fn prev(index: LanMenuButton) -> Self {
+        match index {
+            Self::Host => Self::Back,
+            Self::Join => Self::Host,
+            Self::Watch => Self::Join,
+            Self::Delay => Self::Watch,
+            Self::Back => Self::Delay,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SelectedButton(Option<LanMenuButton>, Interaction);
+
+/// the choices made in the LAN menu.
+#[derive(Resource)]
+pub struct LanSettings {
+    /// the address typed in to join.
+    join_address: String,
+    /// how many ticks to hold back local inputs in LAN bouts, see `Rollback`.
+    pub input_delay: u64,
+}
+
+impl LanSettings {
+    fn delay_label(&self) -> String {
+        format!("Input Delay: {}", self.input_delay)
+    }
+}
+
+#[derive(Component)]
+struct AddressText;
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// waits to be joined, fences as player one.
+    Host,
+    /// joins a host, fences as player two.
+    Client,
+}
+
+impl Role {
+    /// the fighter this machine controls.
+    pub fn player(&self) -> Player {
+        match self {
+            Self::Host => Player::One,
+            Self::Client => Player::Two,
+        }
+    }
+}
+
+/// a LAN bout between this machine and another one.
+#[derive(Resource)]
+pub struct LanSession {
+    socket: UdpSocket,
+    pub role: Role,
+    /// tells this session apart from other hosts in the lobby.
+    id: u64,
+    /// the name the host goes by in other players lobbies, looked up once for the session.
+    name: String,
+    /// the other machine. a host does not know this untill someone joins.
+    peer: Option<SocketAddr>,
+    /// wether the other machine has been heard from yet.
+    pub connected: bool,
+    /// seconds since the other machine was last heard from (or since hello was last sent, while
+    /// joining).
+    silence: f32,
+    /// how many bouts have been started this session.
+    round: u32,
+    /// the current bout, as this machine sees it.
+    rollback: Rollback,
+    /// machines watching a hosts bout.
+    spectators: Vec<Spectator>,
+    /// what ended the current bout, for spectators to follow.
+    outcome: Option<BoutEvent>,
+    /// which hand each fighter fences with. the host picks, and tells the client in its welcome.
+    hands: Handedness,
+    /// how the match is fenced, picked by the host like the hands.
+    rules: MatchRules,
+}
+
+/// someone watching a hosts bout.
+struct Spectator {
+    addr: SocketAddr,
+    /// seconds since they last asked to keep watching.
+    silence: f32,
+}
+
+impl LanSession {
+    fn host(port: u16) -> std::io::Result<Self> {
+        let session = Self::bind(SocketAddr::from(([0, 0, 0, 0], port)), Role::Host, None)?;
+        // needed to announce the bout to the lobby.
+        session.socket.set_broadcast(true)?;
+
+        Ok(session)
+    }
+
+    /// starts joining the bout hosted at `host`.
+    pub fn join(host: SocketAddr) -> std::io::Result<Self> {
+        let session = Self::bind(
+            SocketAddr::from(([0, 0, 0, 0], 0)),
+            Role::Client,
+            Some(host),
+        )?;
+        // say hello straight away
+        session.send(&Message::Hello);
+
+        Ok(session)
+    }
+
+    fn bind(addr: SocketAddr, role: Role, peer: Option<SocketAddr>) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            role,
+            id: Rng::from_time().next_u64(),
+            name: discovery::host_name(),
+            peer,
+            connected: false,
+            silence: 0.0,
+            round: 0,
+            rollback: Rollback::new(role.player(), DEFAULT_INPUT_DELAY),
+            spectators: Vec::new(),
+            outcome: None,
+            hands: Handedness::default(),
+            rules: MatchRules::default(),
+        })
+    }
+
+    fn send(&self, message: &Message) {
+        if let Some(peer) = self.peer {
+            self.send_to(message, peer);
+        }
+    }
+
+    fn send_to(&self, message: &Message, to: SocketAddr) {
+        if let Err(e) = self.socket.send_to(&message.encode(), to) {
+            warn!("failed to send to {to}: {e}");
+        }
+    }
+
+    fn receive(&self) -> Vec<(Message, SocketAddr)> {
+        receive(&self.socket)
+    }
+
+    /// handles messages from machines other than the opponent (people in the lobby, and
+    /// spectators). only a host answers them.
+    fn answer(&mut self, message: Message, from: SocketAddr) {
+        if self.role != Role::Host {
+            debug!("ignoring {message:?} from {from}");
+            return;
+        }
+
+        match message {
+            // someone in the lobby measuring their ping to us.
+            Message::Ping(n) => self.send_to(&Message::Pong(n), from),
+            Message::Watch => {
+                if let Some(spectator) = self.spectators.iter_mut().find(|s| s.addr == from) {
+                    spectator.silence = 0.0;
+                } else if self.spectators.len() < MAX_SPECTATORS {
+                    info!("{from} started watching");
+                    self.spectators.push(Spectator {
+                        addr: from,
+                        silence: 0.0,
+                    });
+                    self.send_to(&Message::Welcome(self.hands, self.rules), from);
+                } else {
+                    debug!("turning away {from}, there are already {MAX_SPECTATORS} spectators");
+                }
+            }
+            Message::Bye => {
+                info!("{from} stopped watching");
+                self.spectators.retain(|spectator| spectator.addr != from);
+            }
+            message => debug!("ignoring {message:?} from {from}"),
+        }
+    }
+}
+
+/// every message waiting on the socket, and who sent it.
+fn receive(socket: &UdpSocket) -> Vec<(Message, SocketAddr)> {
+    let mut messages = Vec::new();
+    let mut buf = [0; MAX_PACKET];
+
+    loop {
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                if let Some(message) = Message::decode(&buf[..len]) {
+                    messages.push((message, from));
+                } else {
+                    debug!("ignoring a malformed packet from {from}");
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => {
+                // windows reports an unreachable peer as an error on the next read.
+                debug!("socket error: {e}");
+                break;
+            }
+        }
+    }
+
+    messages
+}
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+
+/// used to host or join a bout over the LAN
+pub struct LanPlugin;
+
+impl Plugin for LanPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LanSettings {
+            join_address: format!("127.0.0.1:{DEFAULT_PORT}"),
+            input_delay: DEFAULT_INPUT_DELAY,
+        })
+        .add_systems(OnEnter(Screen::Lan), spawn_buttons)
+        .add_systems(OnExit(Screen::Lan), despawn_buttons)
+        .add_systems(Update, keyboard_select.run_if(in_state(Screen::Lan)))
+        .add_systems(Update, mouse_select.run_if(in_state(Screen::Lan)))
+        .add_systems(Update, button_selection.run_if(in_state(Screen::Lan)))
+        .add_systems(Update, type_address.run_if(in_state(Screen::Lan)))
+        .add_systems(
+            Update,
+            handshake
+                .run_if(in_state(Screen::Lan).or_else(in_state(Screen::Lobby)))
+                .run_if(resource_exists::<LanSession>),
+        )
+        .add_systems(
+            Update,
+            discovery::announce.run_if(resource_exists::<LanSession>),
+        )
+        .add_systems(
+            FixedUpdate,
+            exchange_inputs
+                .after(player_movement)
+                .after(player_blade_play)
+                .run_if(resource_exists::<LanSession>),
+        )
+        .add_systems(
+            OnEnter(Screen::NewBout),
+            next_round
+                .after(reset_world)
+                .run_if(resource_exists::<LanSession>),
+        )
+        .add_systems(
+            Update,
+            (spectator::spectate, spectator::keep_watching)
+                .run_if(resource_exists::<SpectatorSession>),
+        )
+        .add_systems(Update, timeout.run_if(resource_exists::<LanSession>))
+        .add_systems(OnEnter(Screen::Welcome), end_session)
+        // changing mode from the victory screen leaves the bout too.
+        .add_systems(OnEnter(Screen::ModeSelect), end_session);
+    }
+}
+
+/// spawns the buttons (and button text) of the LAN menu
+fn spawn_buttons(mut commands: Commands, settings: Res<LanSettings>) {
+    debug!("spawning LAN menu buttons");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ButtonMarker,
+        ))
+        // status line
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Host a bout, or type an address and join or watch one",
+                    TextStyle {
+                        // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 16.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                StatusText,
+            ));
+        })
+        // address to join
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    format!("Join address: {}", settings.join_address),
+                    TextStyle {
+                        // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 16.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                AddressText,
+            ));
+        })
+        // Host button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Host",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LanMenuButton::Host,
+                    ));
+                });
+        })
+        // Join button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Join",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LanMenuButton::Join,
+                    ));
+                });
+        })
+        // Watch button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Watch",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LanMenuButton::Watch,
+                    ));
+                });
+        })
+        // Input delay button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(20.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            settings.delay_label(),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LanMenuButton::Delay,
+                    ));
+                });
+        })
+        // Back button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Back",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LanMenuButton::Back,
+                    ));
+                });
+        });
+
+    commands.insert_resource(SelectedButton(None, Interaction::None));
+}
+
+/// handles using the keyboard to select a button
+fn keyboard_select(menu_input: Res<MenuInput>, mut button_selection: ResMut<SelectedButton>) {
+    if menu_input.back {
+        debug!("pressing back button with keeb");
+        button_selection.0 = Some(LanMenuButton::Back);
+        button_selection.1 = Interaction::Pressed;
+        return;
+    }
+
+    match button_selection.0 {
+        Some(button) => {
+            if menu_input.next {
+                debug!("changing button selection to next button with keeb");
+                button_selection.0 = Some(LanMenuButton::next(button));
+            } else if menu_input.prev {
+                debug!("changing button selection to previous button with keeb");
+                button_selection.0 = Some(LanMenuButton::prev(button));
+            } else if menu_input.press {
+                debug!("pressing button with keeb");
+                button_selection.1 = Interaction::Pressed;
+            }
+        }
+        None => {
+            if menu_input.prev || menu_input.next {
+                debug!("selecting host button with keeb");
+                button_selection.0 = Some(LanMenuButton::Host);
+                button_selection.1 = Interaction::Hovered;
+            }
+        }
+    }
+}
+
+/// handles using the mouse to select a button
+fn mouse_select(
+    mut interaction_query: ButtonInteractions,
+    mut text_query: Query<&LanMenuButton, With<Text>>,
+    mut button_selection: ResMut<SelectedButton>,
+) {
+    for (interaction, children) in &mut interaction_query {
+        let Ok(button_type) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        match *interaction {
+            Interaction::Pressed | Interaction::Hovered => {
+                debug!("selecting with mouse");
+                *button_selection = SelectedButton(Some(*button_type), *interaction);
+            }
+            Interaction::None => {
+                debug!("unselecting with mouse");
+                *button_selection = SelectedButton(None, *interaction);
+            }
+        }
+    }
+}
+
+/// handles changing the selected buttons collor and text
+fn button_selection(
+    mut commands: Commands,
+    mut button_query: Query<(&mut BackgroundColor, &mut BorderColor, &Children), With<Button>>,
+    mut text_query: Query<(&mut Text, &LanMenuButton)>,
+    mut status_query: Query<&mut Text, (With<StatusText>, Without<LanMenuButton>)>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut button_selection: ResMut<SelectedButton>,
+    mut settings: ResMut<LanSettings>,
+) {
+    let select_prefix = "> ";
+
+    for (mut color, mut border_color, children) in &mut button_query {
+        let Ok((mut text, button_type)) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        if Some(*button_type) == button_selection.0 {
+            match button_selection.1 {
+                Interaction::Pressed => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = PRESSED_BUTTON.into();
+
+                    let pressed = press_button(*button_type, &mut commands, &mut settings);
+
+                    if *button_type == LanMenuButton::Delay {
+                        text.sections[0].value = settings.delay_label();
+                    }
+
+                    match pressed {
+                        Pressed::Go(screen) => next_state.set(screen),
+                        Pressed::Status(status) => {
+                            if let Ok(mut status_text) = status_query.get_single_mut() {
+                                status_text.sections[0].value = status;
+                            }
+                        }
+                    }
+
+                    button_selection.1 = Interaction::Hovered;
+                }
+                Interaction::Hovered => {
+                    if !text.sections[0].value.starts_with(select_prefix) {
+                        text.sections[0].value =
+                            format!("{select_prefix}{}", text.sections[0].value);
+                    }
+
+                    *color = HOVERED_BUTTON.into();
+                    border_color.0 = Color::WHITE;
+                }
+                Interaction::None => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = NORMAL_BUTTON.into();
+                }
+            }
+        } else {
+            text.sections[0].value = text.sections[0]
+                .value
+                .strip_prefix(select_prefix)
+                .unwrap_or(text.sections[0].value.as_str())
+                .to_string();
+            *color = NORMAL_BUTTON.into();
+            border_color.0 = Color::BLACK;
+        }
+    }
+}
+
+/// called by mouse select or keyboard select. used to envoke the buttons function.
+fn press_button(
+    button: LanMenuButton,
+    commands: &mut Commands,
+    settings: &mut LanSettings,
+) -> Pressed {
+    match button {
+        LanMenuButton::Host => match LanSession::host(DEFAULT_PORT) {
+            Ok(session) => {
+                info!("hosting a LAN bout on port {DEFAULT_PORT}");
+                commands.insert_resource(session);
+                Pressed::Status(format!("Waiting for an opponent on port {DEFAULT_PORT}..."))
+            }
+            Err(e) => {
+                error!("could not host on port {DEFAULT_PORT}: {e}");
+                Pressed::Status(format!("Could not host: {e}"))
+            }
+        },
+        LanMenuButton::Join => match settings.join_address.parse::<SocketAddr>() {
+            Ok(host) => match LanSession::join(host) {
+                Ok(session) => {
+                    info!("joining the LAN bout at {host}");
+                    commands.insert_resource(session);
+                    Pressed::Status(format!("Joining {host}..."))
+                }
+                Err(e) => {
+                    error!("could not open a socket to join {host}: {e}");
+                    Pressed::Status(format!("Could not join: {e}"))
+                }
+            },
+            Err(_) => Pressed::Status(format!(
+                "\"{}\" is not an address (ip:port)",
+                settings.join_address
+            )),
+        },
+        LanMenuButton::Watch => match settings.join_address.parse::<SocketAddr>() {
+            Ok(host) => match SpectatorSession::watch(host) {
+                Ok(session) => {
+                    info!("watching the LAN bout at {host}");
+                    commands.insert_resource(session);
+                    Pressed::Status(format!("Watching {host}, waiting for the bout to start..."))
+                }
+                Err(e) => {
+                    error!("could not open a socket to watch {host}: {e}");
+                    Pressed::Status(format!("Could not watch: {e}"))
+                }
+            },
+            Err(_) => Pressed::Status(format!(
+                "\"{}\" is not an address (ip:port)",
+                settings.join_address
+            )),
+        },
+        LanMenuButton::Delay => {
+            settings.input_delay = (settings.input_delay + 1) % (MAX_INPUT_DELAY + 1);
+            Pressed::Status(format!(
+                "Your inputs will land {} ms after you press them",
+                (settings.input_delay as f32 * TICK * 1000.0).round()
+            ))
+        }
+        LanMenuButton::Back => {
+            commands.remove_resource::<LanSession>();
+            commands.remove_resource::<SpectatorSession>();
+            Pressed::Go(Screen::Lobby)
+        }
+    }
+}
+
+/// handles typing in the address to join
+fn type_address(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut settings: ResMut<LanSettings>,
+    mut address_query: Query<&mut Text, With<AddressText>>,
+) {
+    let before = settings.join_address.clone();
+
+    for event in characters.read() {
+        settings.join_address.extend(
+            event
+                .char
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '[' | ']')),
+        );
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        settings.join_address.pop();
+    }
+
+    if settings.join_address != before
+        && let Ok(mut text) = address_query.get_single_mut()
+    {
+        text.sections[0].value = format!("Join address: {}", settings.join_address);
+    }
+}
+
+/// waits for the other machine to show up, then starts the first bout.
+fn handshake(
+    mut session: ResMut<LanSession>,
+    mut game_mode: ResMut<GameMode>,
+    mut bout: ResMut<Bout>,
+    mut next_state: ResMut<NextState<Screen>>,
+    time: Res<Time>,
+    handedness: Res<Handedness>,
+    rules: Res<MatchRules>,
+) {
+    for (message, from) in session.receive() {
+        match (session.role, message) {
+            (Role::Host, Message::Hello) => {
+                info!("{from} joined the bout");
+                session.peer = Some(from);
+                session.connected = true;
+                session.hands = *handedness;
+                session.rules = *rules;
+                session.send(&Message::Welcome(session.hands, session.rules));
+                *game_mode = GameMode::LanHost;
+            }
+            (Role::Client, Message::Welcome(hands, rules)) if Some(from) == session.peer => {
+                info!("joined the bout hosted by {from}");
+                session.connected = true;
+                session.hands = hands;
+                session.rules = rules;
+                *game_mode = GameMode::LanClient;
+            }
+            (_, message) => session.answer(message, from),
+        }
+    }
+
+    if session.connected {
+        session.silence = 0.0;
+        bout.state.reset_scores();
+        next_state.set(Screen::NewBout);
+    } else if session.role == Role::Client {
+        session.silence += time.delta_seconds();
+
+        if session.silence >= HELLO_INTERVAL {
+            session.silence = 0.0;
+            session.send(&Message::Hello);
+        }
+    }
+}
+
+/// trades inputs with the other machine and, during a bout, steps the rollback simulation.
+/// keeps trading between bouts so the other machine knows this one is still there.
+fn exchange_inputs(
+    mut session: ResMut<LanSession>,
+    mut bout: ResMut<Bout>,
+    inputs: Res<BoutInputs>,
+    screen: Res<State<Screen>>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut bout_events: EventWriter<BoutEvent>,
+    mut tick_inputs: EventWriter<TickInputs>,
+) {
+    if !session.connected {
+        return;
+    }
+
+    for (message, from) in session.receive() {
+        if Some(from) != session.peer {
+            session.answer(message, from);
+            continue;
+        }
+
+        match message {
+            Message::Inputs {
+                round,
+                start,
+                ack,
+                inputs,
+            } => {
+                session.silence = 0.0;
+
+                if round == session.round {
+                    session.rollback.receive(start, &inputs, ack);
+                } else if round > session.round
+                    && matches!(*screen.get(), Screen::TouchScored | Screen::Victory)
+                {
+                    info!("the other fencer started the next bout");
+                    next_state.set(Screen::NewBout);
+                }
+            }
+            // the welcome got lost, say it again.
+            Message::Hello if session.role == Role::Host => {
+                session.send(&Message::Welcome(session.hands, session.rules))
+            }
+            Message::Bye => {
+                info!("{from} left the bout");
+                session.silence = TIMEOUT;
+            }
+            message => debug!("ignoring {message:?} from {from}"),
+        }
+    }
+
+    if *screen.get() == Screen::Game {
+        let local = inputs.get(session.role.player());
+
+        for event in session.rollback.advance(&mut bout, local) {
+            // the referee calls halt on it, and moves the game on.
+            if next_screen(event).is_some() {
+                session.outcome = Some(event);
+            }
+
+            bout_events.send(event);
+        }
+
+        for (p1, p2) in session.rollback.take_confirmed_inputs() {
+            tick_inputs.send(TickInputs { p1, p2 });
+        }
+    }
+
+    if !session.spectators.is_empty() {
+        // spectators only see what can no longer be rolled back.
+        let confirmed = session.rollback.confirmed_bout().unwrap_or(&bout);
+        let snapshot = Message::Snapshot(Snapshot {
+            round: session.round,
+            tick: confirmed.tick,
+            p1: confirmed.p1.clone(),
+            p2: confirmed.p2.clone(),
+            state: confirmed.state.clone(),
+            over: confirmed.over,
+            outcome: session.outcome,
+        });
+
+        for spectator in session.spectators.iter() {
+            session.send_to(&snapshot, spectator.addr);
+        }
+    }
+
+    let (start, inputs) = session.rollback.outgoing();
+    session.send(&Message::Inputs {
+        round: session.round,
+        start,
+        ack: session.rollback.ack(),
+        inputs,
+    });
+}
+
+/// starts the rollback simulation of the next bout over, with the fighters holding their
+/// swords in the hands the host picked, fencing by the hosts rules.
+fn next_round(mut session: ResMut<LanSession>, settings: Res<LanSettings>, mut bout: ResMut<Bout>) {
+    (bout.p1.handed, bout.p2.handed) = (session.hands.p1, session.hands.p2);
+    bout.state.set_rules(session.rules);
+    session.round += 1;
+    session.rollback = Rollback::new(session.role.player(), settings.input_delay);
+    session.outcome = None;
+}
+
+/// gives up on the other machine, or any spectators, if they have gone quiet.
+fn timeout(
+    mut session: ResMut<LanSession>,
+    time: Res<Time>,
+    mut next_state: ResMut<NextState<Screen>>,
+) {
+    for spectator in session.spectators.iter_mut() {
+        spectator.silence += time.delta_seconds();
+    }
+
+    session
+        .spectators
+        .retain(|spectator| spectator.silence < TIMEOUT);
+
+    if !session.connected {
+        return;
+    }
+
+    session.silence += time.delta_seconds();
+
+    if session.silence >= TIMEOUT {
+        error!("lost the connection to the other machine");
+        next_state.set(Screen::Welcome);
+    }
+}
+
+/// leaves the LAN bout (if there is one), or stops watching it.
+fn end_session(
+    mut commands: Commands,
+    session: Option<Res<LanSession>>,
+    spectating: Option<Res<SpectatorSession>>,
+    mut game_mode: ResMut<GameMode>,
+) {
+    if let Some(session) = session {
+        session.send(&Message::Bye);
+
+        for spectator in session.spectators.iter() {
+            session.send_to(&Message::Bye, spectator.addr);
+        }
+
+        commands.remove_resource::<LanSession>();
+    }
+
+    if let Some(spectating) = spectating {
+        spectating.send(&Message::Bye);
+        commands.remove_resource::<SpectatorSession>();
+    }
+
+    *game_mode = GameMode::VsComputer;
+}
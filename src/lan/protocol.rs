@@ -0,0 +1,804 @@
+use crate::{
+    bout::{BoutEvent, FighterInput},
+    fighter::*,
+    state::{GameState, Handedness, MatchRules, Phrase, Riposte, Score, ScoredTouch, Screen},
+};
+use bevy::prelude::*;
+
+/// every packet starts with these bytes, anything else on the port is ignored.
+const MAGIC: [u8; 2] = *b"FG";
+/// bump whenever the layout of a message changes.
+const VERSION: u8 = 13;
+/// longest host name sent in an announcement.
+const MAX_NAME_LEN: usize = 32;
+/// most inputs sent in a single packet.
+pub const MAX_INPUTS: usize = 64;
+/// the most bytes a packet can be, the receive buffer is this big. every message has to fit.
+pub const MAX_PACKET: usize = 512;
+
+const GAURDS: [Gaurd; 4] = [Gaurd::Right, Gaurd::Down, Gaurd::Left, Gaurd::Up];
+const MOVES: [Move; 10] = [
+    Move::Advance,
+    Move::Retreat,
+    Move::Lunge,
+    Move::EnGarde,
+    Move::Beat,
+    Move::DoubleAdvance,
+    Move::DoubleRetreat,
+    Move::Fleche,
+    Move::Balestra,
+    Move::Riposte,
+];
+const STANCES: [Stance; 3] = [Stance::Offence, Stance::Defence, Stance::Lunged];
+const HANDS: [Handed; 2] = [Handed::Right, Handed::Left];
+const PLAYERS: [Player; 2] = [Player::One, Player::Two];
+const PHRASES: [Phrase; 3] = [Phrase::Attack, Phrase::Riposte, Phrase::CounterRiposte];
+const SCREENS: [Screen; 15] = [
+    Screen::Setup,
+    Screen::Welcome,
+    Screen::ModeSelect,
+    Screen::Lobby,
+    Screen::Lan,
+    Screen::Game,
+    Screen::PauseMenu,
+    Screen::NewBout,
+    Screen::Victory,
+    Screen::Controls,
+    Screen::HowToPlay,
+    Screen::TouchScored,
+    Screen::MatchWon,
+    Screen::Replay,
+    Screen::ExitGame,
+];
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// sent by a client that wants to join the host.
+    Hello,
+    /// the host accepted the client, and picked which hand each fighter fences with and how the
+    /// match is fenced.
+    Welcome(Handedness, MatchRules),
+    /// the senders inputs for the ticks `start..`, every one the receiver has not yet
+    /// acknowledged. `ack` is the last tick the sender has every one of the receivers inputs for.
+    Inputs {
+        /// how many bouts the sender has started this session.
+        round: u32,
+        start: u64,
+        ack: u64,
+        inputs: Vec<FighterInput>,
+    },
+    /// the state of the bout, as seen by the host.
+    Snapshot(Snapshot),
+    /// the sender is leaving.
+    Bye,
+    /// a host telling the network that it is waiting for an opponent. `id` tells apart hosts
+    /// (and spots the same host heard over more than one route).
+    Announce {
+        id: u64,
+        name: String,
+        touches_to_win: u8,
+        /// 1 while the host waits for an opponent, 2 once the bout is under way.
+        fencers: u8,
+        spectators: u8,
+    },
+    /// asks a host to answer with a pong carrying the same number, to measure latency.
+    Ping(u64),
+    Pong(u64),
+    /// sent by a spectator that wants (or still wants) the hosts snapshots.
+    Watch,
+}
+
+/// everything a client needs to show the hosts bout.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// how many bouts the host has started this session.
+    pub round: u32,
+    pub tick: u64,
+    pub p1: Fighter,
+    pub p2: Fighter,
+    pub state: GameState,
+    pub over: bool,
+    /// what ended the bout, on the host.
+    pub outcome: Option<BoutEvent>,
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::with_capacity(128));
+        w.0.extend_from_slice(&MAGIC);
+        w.u8(VERSION);
+
+        match self {
+            Self::Hello => w.u8(0),
+            Self::Welcome(hands, rules) => {
+                w.u8(1);
+                w.variant(&HANDS, hands.p1);
+                w.variant(&HANDS, hands.p2);
+                w.rules(rules);
+            }
+            Self::Inputs {
+                round,
+                start,
+                ack,
+                inputs,
+            } => {
+                w.u8(2);
+                w.u32(*round);
+                w.u64(*start);
+                w.u64(*ack);
+
+                let inputs = &inputs[..inputs.len().min(MAX_INPUTS)];
+                w.u8(inputs.len() as u8);
+                inputs.iter().for_each(|input| w.input(input));
+            }
+            Self::Snapshot(snapshot) => {
+                w.u8(3);
+                w.u32(snapshot.round);
+                w.u64(snapshot.tick);
+                w.fighter(&snapshot.p1);
+                w.fighter(&snapshot.p2);
+                w.state(&snapshot.state);
+                w.bool(snapshot.over);
+                w.outcome(&snapshot.outcome);
+            }
+            Self::Bye => w.u8(4),
+            Self::Announce {
+                id,
+                name,
+                touches_to_win,
+                fencers,
+                spectators,
+            } => {
+                w.u8(5);
+                w.u64(*id);
+                w.string(name);
+                w.u8(*touches_to_win);
+                w.u8(*fencers);
+                w.u8(*spectators);
+            }
+            Self::Ping(n) => {
+                w.u8(6);
+                w.u64(*n);
+            }
+            Self::Pong(n) => {
+                w.u8(7);
+                w.u64(*n);
+            }
+            Self::Watch => w.u8(8),
+        }
+
+        w.0
+    }
+
+    /// reads a message from a packet. returns None for anything malformed or from another
+    /// version of the game.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader(bytes);
+
+        if r.take(2)? != MAGIC || r.u8()? != VERSION {
+            return None;
+        }
+
+        let message = match r.u8()? {
+            0 => Self::Hello,
+            1 => Self::Welcome(
+                Handedness {
+                    p1: r.variant(&HANDS)?,
+                    p2: r.variant(&HANDS)?,
+                },
+                r.rules()?,
+            ),
+            2 => {
+                let round = r.u32()?;
+                let start = r.u64()?;
+                let ack = r.u64()?;
+                let count = r.u8()? as usize;
+
+                Self::Inputs {
+                    round,
+                    start,
+                    ack,
+                    inputs: (0..count).map(|_| r.input()).collect::<Option<_>>()?,
+                }
+            }
+            3 => Self::Snapshot(Snapshot {
+                round: r.u32()?,
+                tick: r.u64()?,
+                p1: r.fighter()?,
+                p2: r.fighter()?,
+                state: r.state()?,
+                over: r.bool()?,
+                outcome: r.outcome()?,
+            }),
+            4 => Self::Bye,
+            5 => Self::Announce {
+                id: r.u64()?,
+                name: r.string()?,
+                touches_to_win: r.u8()?,
+                fencers: r.u8()?,
+                spectators: r.u8()?,
+            },
+            6 => Self::Ping(r.u64()?),
+            7 => Self::Pong(r.u64()?),
+            8 => Self::Watch,
+            _ => return None,
+        };
+
+        r.0.is_empty().then_some(message)
+    }
+}
+
+/// writes the pieces of a message (or anything else kept in the same encoding, like replays).
+pub struct Writer(pub Vec<u8>);
+
+impl Writer {
+    pub fn u8(&mut self, n: u8) {
+        self.0.push(n);
+    }
+
+    pub fn u16(&mut self, n: u16) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, n: u64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, n: f32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, b: bool) {
+        self.u8(b as u8);
+    }
+
+    /// writes at most `MAX_NAME_LEN` bytes of `s`, without splitting a character.
+    pub fn string(&mut self, s: &str) {
+        let mut len = s.len().min(MAX_NAME_LEN);
+
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        self.u8(len as u8);
+        self.0.extend_from_slice(&s.as_bytes()[..len]);
+    }
+
+    /// writes the position of `value` in `all`.
+    pub fn variant<T: PartialEq + std::fmt::Debug>(&mut self, all: &[T], value: T) {
+        let i = all.iter().position(|v| *v == value);
+        debug_assert!(i.is_some(), "{value:?} is missing from its variant table");
+
+        self.u8(i.unwrap_or_default() as u8);
+    }
+
+    pub fn option<T: PartialEq + std::fmt::Debug>(&mut self, all: &[T], value: Option<T>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.variant(all, value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    pub fn input(&mut self, input: &FighterInput) {
+        self.option(&MOVES, input.action);
+        self.option(&GAURDS, input.gaurd);
+        self.bool(input.crouch);
+        self.option(&STANCES, input.stance);
+    }
+
+    pub fn fighter(&mut self, fighter: &Fighter) {
+        self.variant(&GAURDS, fighter.gaurd);
+        self.f32(fighter.position);
+        self.variant(&STANCES, fighter.stance);
+        self.variant(&STANCES, fighter.en_garde);
+        self.variant(&HANDS, fighter.handed);
+        self.variant(&PLAYERS, fighter.player);
+        self.bool(fighter.parrying);
+        self.variant(&MOVES, fighter.action.act);
+        self.f32(fighter.action.block_for);
+        self.f32(fighter.action.moved);
+        self.f32(fighter.action.dir_vec.x);
+        self.bool(fighter.crouched);
+    }
+
+    pub fn score(&mut self, score: &Score) {
+        self.u8(score.touches);
+        self.u16(score.matches);
+    }
+
+    pub fn riposte(&mut self, riposte: &Option<Riposte>) {
+        self.bool(riposte.is_some());
+
+        if let Some(riposte) = riposte {
+            self.variant(&PLAYERS, riposte.player);
+            self.u16(riposte.ticks_left);
+            self.u8(riposte.parries);
+            self.bool(riposte.thrown);
+        }
+    }
+
+    pub fn rules(&mut self, rules: &MatchRules) {
+        self.u8(rules.touches_to_win);
+        self.bool(rules.period.is_some());
+        self.u16(rules.period.unwrap_or_default());
+        self.u8(rules.periods);
+        self.u16(rules.break_time);
+    }
+
+    pub fn timeline(&mut self, timeline: &[ScoredTouch]) {
+        self.u8(timeline.len() as u8);
+
+        for touch in timeline {
+            self.variant(&PLAYERS, touch.player);
+            self.variant(&PHRASES, touch.phrase);
+            self.u8(touch.period);
+            self.u16(touch.seconds);
+        }
+    }
+
+    pub fn state(&mut self, state: &GameState) {
+        self.option(&PLAYERS, state.row);
+        self.option(&PLAYERS, state.lunger);
+        self.riposte(&state.riposte);
+        self.score(&state.p1_score);
+        self.score(&state.p2_score);
+        self.rules(&state.rules);
+        self.u8(state.period);
+        self.u32(state.clock);
+        self.option(&PLAYERS, state.priority);
+        self.option(&PLAYERS, state.winner);
+        self.timeline(&state.timeline);
+        self.timeline(&state.last_match);
+    }
+
+    /// writes what ended the bout, if anything has.
+    pub fn outcome(&mut self, outcome: &Option<BoutEvent>) {
+        let Some(event) = outcome else {
+            self.u8(0);
+            return;
+        };
+
+        match *event {
+            BoutEvent::Touch(player, screen) => {
+                self.u8(1);
+                self.variant(&PLAYERS, player);
+                self.variant(&SCREENS, screen);
+            }
+            BoutEvent::Parry(player) => {
+                self.u8(2);
+                self.variant(&PLAYERS, player);
+            }
+            BoutEvent::Beat(player) => {
+                self.u8(3);
+                self.variant(&PLAYERS, player);
+            }
+            BoutEvent::SideFlip => self.u8(4),
+            BoutEvent::OutOfBounds(player) => {
+                self.u8(5);
+                self.variant(&PLAYERS, player);
+            }
+            BoutEvent::Simultaneous => self.u8(6),
+            BoutEvent::TimeUp(screen) => {
+                self.u8(7);
+                self.variant(&SCREENS, screen);
+            }
+        }
+    }
+}
+
+/// reads back what a `Writer` wrote. every read returns None if the bytes run out or make no
+/// sense.
+pub struct Reader<'a>(pub &'a [u8]);
+
+impl Reader<'_> {
+    pub fn take(&mut self, n: usize) -> Option<&[u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+
+        Some(head)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn string(&mut self) -> Option<String> {
+        let len = self.u8()? as usize;
+
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    pub fn variant<T: Copy>(&mut self, all: &[T]) -> Option<T> {
+        all.get(self.u8()? as usize).copied()
+    }
+
+    /// the outer Option is None if the packet is malformed.
+    pub fn option<T: Copy>(&mut self, all: &[T]) -> Option<Option<T>> {
+        match self.u8()? {
+            0 => Some(None),
+            1 => Some(Some(self.variant(all)?)),
+            _ => None,
+        }
+    }
+
+    pub fn input(&mut self) -> Option<FighterInput> {
+        Some(FighterInput {
+            action: self.option(&MOVES)?,
+            gaurd: self.option(&GAURDS)?,
+            crouch: self.bool()?,
+            stance: self.option(&STANCES)?,
+        })
+    }
+
+    pub fn fighter(&mut self) -> Option<Fighter> {
+        let gaurd = self.variant(&GAURDS)?;
+        let position = self.f32()?;
+        let stance = self.variant(&STANCES)?;
+        let en_garde = self.variant(&STANCES)?;
+        let handed = self.variant(&HANDS)?;
+        let player = self.variant(&PLAYERS)?;
+        let parrying = self.bool()?;
+        let act = self.variant(&MOVES)?;
+        let block_for = self.f32()?;
+        let moved = self.f32()?;
+        let dir_x = self.f32()?;
+        let crouched = self.bool()?;
+
+        Some(Fighter {
+            gaurd,
+            position,
+            stance,
+            en_garde,
+            handed,
+            player,
+            // who controls a fighter is up to each machine, so it is not sent.
+            controller: Controller::Remote,
+            parrying,
+            action: Action {
+                act,
+                block_for,
+                moved,
+                dir_vec: Vec3::new(dir_x, 0.0, 0.0),
+            },
+            crouched,
+        })
+    }
+
+    pub fn score(&mut self) -> Option<Score> {
+        Some(Score {
+            touches: self.u8()?,
+            matches: self.u16()?,
+        })
+    }
+
+    pub fn riposte(&mut self) -> Option<Option<Riposte>> {
+        if !self.bool()? {
+            return Some(None);
+        }
+
+        Some(Some(Riposte {
+            player: self.variant(&PLAYERS)?,
+            ticks_left: self.u16()?,
+            parries: self.u8()?,
+            thrown: self.bool()?,
+        }))
+    }
+
+    pub fn rules(&mut self) -> Option<MatchRules> {
+        let touches_to_win = self.u8()?;
+        let timed = self.bool()?;
+        let period = self.u16()?;
+
+        Some(MatchRules {
+            touches_to_win,
+            period: timed.then_some(period),
+            periods: self.u8()?,
+            break_time: self.u16()?,
+        })
+    }
+
+    pub fn timeline(&mut self) -> Option<Vec<ScoredTouch>> {
+        let len = self.u8()?;
+
+        (0..len)
+            .map(|_| {
+                Some(ScoredTouch {
+                    player: self.variant(&PLAYERS)?,
+                    phrase: self.variant(&PHRASES)?,
+                    period: self.u8()?,
+                    seconds: self.u16()?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn state(&mut self) -> Option<GameState> {
+        Some(GameState {
+            row: self.option(&PLAYERS)?,
+            lunger: self.option(&PLAYERS)?,
+            riposte: self.riposte()?,
+            p1_score: self.score()?,
+            p2_score: self.score()?,
+            rules: self.rules()?,
+            period: self.u8()?,
+            clock: self.u32()?,
+            priority: self.option(&PLAYERS)?,
+            winner: self.option(&PLAYERS)?,
+            timeline: self.timeline()?,
+            last_match: self.timeline()?,
+        })
+    }
+
+    /// the outer Option is None if the packet is malformed.
+    pub fn outcome(&mut self) -> Option<Option<BoutEvent>> {
+        let event = match self.u8()? {
+            0 => return Some(None),
+            1 => BoutEvent::Touch(self.variant(&PLAYERS)?, self.variant(&SCREENS)?),
+            2 => BoutEvent::Parry(self.variant(&PLAYERS)?),
+            3 => BoutEvent::Beat(self.variant(&PLAYERS)?),
+            4 => BoutEvent::SideFlip,
+            5 => BoutEvent::OutOfBounds(self.variant(&PLAYERS)?),
+            6 => BoutEvent::Simultaneous,
+            7 => BoutEvent::TimeUp(self.variant(&SCREENS)?),
+            _ => return None,
+        };
+
+        Some(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the rules with the most touches to win, which makes for the longest timelines, fenced
+    /// with a clock.
+    fn longest_rules() -> MatchRules {
+        let mut rules = MatchRules::default();
+        let mut longest = rules;
+
+        for _ in 0..8 {
+            rules = rules.next_touches();
+
+            if rules.touches_to_win > longest.touches_to_win {
+                longest = rules;
+            }
+        }
+
+        longest.next_clock()
+    }
+
+    /// who controls a fighter is not sent, it always arrives as remote.
+    fn fighter(player: Player) -> Fighter {
+        let mut fighter = Fighter::new(player);
+        fighter.controller = Controller::Remote;
+        fighter
+    }
+
+    fn touches(count: usize) -> Vec<ScoredTouch> {
+        (0..count)
+            .map(|i| ScoredTouch {
+                player: PLAYERS[i % 2],
+                phrase: PHRASES[i % PHRASES.len()],
+                period: 3,
+                seconds: 179,
+            })
+            .collect()
+    }
+
+    /// a snapshot with every optional part filled in, and both timelines as long as a match
+    /// can make them.
+    fn busy_snapshot() -> Snapshot {
+        let rules = longest_rules();
+        let most = rules.touches_to_win as usize * 2 - 1;
+
+        let mut p1 = fighter(Player::One);
+        p1.action = Action::from(Move::Riposte);
+        p1.handed = Handed::Left;
+        p1.crouched = true;
+        let mut p2 = fighter(Player::Two);
+        p2.gaurd = Gaurd::Up;
+        p2.parrying = true;
+        p2.set_stance(Stance::Defence);
+
+        let mut state = GameState::new();
+        state.set_rules(rules);
+        state.row = Some(Player::One);
+        state.lunger = Some(Player::Two);
+        state.riposte = Some(Riposte {
+            player: Player::One,
+            ticks_left: 20,
+            parries: 2,
+            thrown: true,
+        });
+        state.priority = Some(Player::Two);
+        state.winner = Some(Player::One);
+        // the winning touch moves the timeline to the last match, so one is always shorter.
+        state.timeline = touches(most - 1);
+        state.last_match = touches(most);
+
+        Snapshot {
+            round: u32::MAX,
+            tick: 12_345,
+            p1,
+            p2,
+            state,
+            over: true,
+            outcome: Some(BoutEvent::Touch(Player::One, Screen::MatchWon)),
+        }
+    }
+
+    fn full_inputs() -> Message {
+        let inputs = (0..MAX_INPUTS)
+            .map(|i| FighterInput {
+                action: Some(MOVES[i % MOVES.len()]),
+                gaurd: Some(GAURDS[i % GAURDS.len()]),
+                crouch: i % 3 == 0,
+                stance: Some(STANCES[i % 2]),
+            })
+            .collect();
+
+        Message::Inputs {
+            round: 7,
+            start: u64::MAX - 100,
+            ack: 42,
+            inputs,
+        }
+    }
+
+    /// decoding what was encoded gives back the same message.
+    fn assert_round_trips(message: Message) {
+        let bytes = message.encode();
+        let decoded = Message::decode(&bytes);
+
+        assert_eq!(format!("{decoded:?}"), format!("{:?}", Some(message)));
+    }
+
+    #[test]
+    fn every_message_round_trips() {
+        let mut rules = longest_rules();
+        rules.break_time = 90;
+
+        for message in [
+            Message::Hello,
+            Message::Welcome(
+                Handedness {
+                    p1: Handed::Left,
+                    p2: Handed::Right,
+                },
+                rules,
+            ),
+            full_inputs(),
+            Message::Inputs {
+                round: 0,
+                start: 0,
+                ack: 0,
+                inputs: Vec::new(),
+            },
+            Message::Snapshot(busy_snapshot()),
+            Message::Snapshot(Snapshot {
+                round: 0,
+                tick: 0,
+                p1: fighter(Player::One),
+                p2: fighter(Player::Two),
+                state: GameState::new(),
+                over: false,
+                outcome: None,
+            }),
+            Message::Bye,
+            Message::Announce {
+                id: 0xDEAD_BEEF,
+                name: "piste".to_string(),
+                touches_to_win: 15,
+                fencers: 2,
+                spectators: 3,
+            },
+            Message::Ping(9),
+            Message::Pong(u64::MAX),
+            Message::Watch,
+        ] {
+            assert_round_trips(message);
+        }
+    }
+
+    #[test]
+    fn every_outcome_round_trips() {
+        for outcome in [
+            BoutEvent::Touch(Player::Two, Screen::TouchScored),
+            BoutEvent::Parry(Player::One),
+            BoutEvent::Beat(Player::Two),
+            BoutEvent::SideFlip,
+            BoutEvent::Simultaneous,
+            BoutEvent::TimeUp(Screen::Victory),
+            BoutEvent::OutOfBounds(Player::One),
+        ] {
+            let mut snapshot = busy_snapshot();
+            snapshot.outcome = Some(outcome);
+            assert_round_trips(Message::Snapshot(snapshot));
+        }
+    }
+
+    #[test]
+    fn long_names_are_cut_short() {
+        let name = "é".repeat(MAX_NAME_LEN);
+        let message = Message::Announce {
+            id: 1,
+            name,
+            touches_to_win: 5,
+            fencers: 1,
+            spectators: 0,
+        };
+
+        let Some(Message::Announce { name, .. }) = Message::decode(&message.encode()) else {
+            panic!("the announcement did not decode");
+        };
+        assert!(name.len() <= MAX_NAME_LEN);
+        assert!(name.chars().all(|c| c == 'é'));
+    }
+
+    #[test]
+    fn largest_messages_fit_in_a_packet() {
+        let inputs = full_inputs().encode();
+        let snapshot = Message::Snapshot(busy_snapshot()).encode();
+
+        assert!(
+            inputs.len() <= MAX_PACKET,
+            "inputs are {} bytes",
+            inputs.len()
+        );
+        assert!(
+            snapshot.len() <= MAX_PACKET,
+            "a snapshot is {} bytes",
+            snapshot.len()
+        );
+    }
+
+    #[test]
+    fn other_versions_and_junk_are_ignored() {
+        let mut bytes = Message::Hello.encode();
+        bytes[2] = VERSION.wrapping_add(1);
+        assert!(Message::decode(&bytes).is_none());
+
+        let mut bytes = Message::Ping(1).encode();
+        bytes.push(0);
+        assert!(Message::decode(&bytes).is_none());
+
+        let bytes = Message::Snapshot(busy_snapshot()).encode();
+        assert!(Message::decode(&bytes[..bytes.len() - 1]).is_none());
+    }
+}
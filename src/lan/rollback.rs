@@ -0,0 +1,495 @@
+//! keeps two machines fencing the same bout without waiting on each other. every machine runs
+//! the whole simulation, guessing that the other fencer keeps doing whatever they last did. when
+//! their real input turns up and the guess was wrong, the bout is rolled back to the tick before
+//! and simulated forward again.
+use crate::{
+    bout::{Bout, BoutEvent, FighterInput},
+    fighter::Player,
+};
+use std::collections::BTreeMap;
+
+/// how many ticks the simulation may run ahead of the other machines inputs before it waits.
+pub const MAX_ROLLBACK: u64 = 16;
+/// the input delay used unless the player picks another.
+pub const DEFAULT_INPUT_DELAY: u64 = 2;
+/// the most input delay (in ticks) a player can pick.
+pub const MAX_INPUT_DELAY: u64 = 8;
+
+/// one side of a rollback bout. the tick numbers used here count from the start of the bout,
+/// and keep counting once the bout is over.
+#[derive(Debug, Clone)]
+pub struct Rollback {
+    local: Player,
+    /// how many ticks after it was pressed the local input is used. hides latency from the other
+    /// machine at the cost of a little responsiveness.
+    input_delay: u64,
+    /// ticks simulated so far, including guessed ones.
+    frame: u64,
+    /// the local inputs, by tick. kept untill the other machine has them, and they can no longer
+    /// be rolled back to.
+    local_inputs: BTreeMap<u64, FighterInput>,
+    /// the other machines inputs, by tick.
+    remote_inputs: BTreeMap<u64, FighterInput>,
+    /// the last tick every one of the other machines inputs has arrived for.
+    remote_known: u64,
+    /// the last tick the other machine has every one of the local inputs for.
+    acked: u64,
+    /// the last tick simulated with nothing but real inputs. its events are final.
+    confirmed: u64,
+    /// the remote input each unconfirmed tick was simulated with.
+    used: BTreeMap<u64, FighterInput>,
+    /// the bout as it was after each tick, from `confirmed` on.
+    snapshots: BTreeMap<u64, Bout>,
+    /// what happened on each unconfirmed tick.
+    pending: BTreeMap<u64, Vec<BoutEvent>>,
+    /// both fighters inputs (player one first) for every tick made final since they were last
+    /// taken.
+    confirmed_inputs: Vec<(FighterInput, FighterInput)>,
+    /// the earliest tick that was simulated with a wrong guess.
+    rollback_to: Option<u64>,
+    /// how many times the bout was rolled back.
+    pub rollbacks: u64,
+    /// how many ticks were simulated again because of a roll back.
+    pub resimulated: u64,
+    /// how many ticks were spent waiting on the other machine.
+    pub stalls: u64,
+}
+
+impl Rollback {
+    pub fn new(local: Player, input_delay: u64) -> Self {
+        // the first few ticks happen before any input could have been delayed into them.
+        let local_inputs = (1..=input_delay)
+            .map(|frame| (frame, FighterInput::default()))
+            .collect();
+
+        Self {
+            local,
+            input_delay,
+            frame: 0,
+            local_inputs,
+            remote_inputs: BTreeMap::new(),
+            remote_known: 0,
+            acked: 0,
+            confirmed: 0,
+            used: BTreeMap::new(),
+            snapshots: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            confirmed_inputs: Vec::new(),
+            rollback_to: None,
+            rollbacks: 0,
+            resimulated: 0,
+            stalls: 0,
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn input_delay(&self) -> u64 {
+        self.input_delay
+    }
+
+    /// the bout as of the last tick simulated with nothing but real inputs.
+    pub fn confirmed_bout(&self) -> Option<&Bout> {
+        self.snapshots.get(&self.confirmed)
+    }
+
+    /// takes in inputs from the other machine for the ticks `start..`, and the last tick it has
+    /// every local input for.
+    pub fn receive(&mut self, start: u64, inputs: &[FighterInput], ack: u64) {
+        for (frame, input) in (start..).zip(inputs.iter().copied()) {
+            if frame <= self.remote_known {
+                continue;
+            }
+
+            self.remote_inputs.insert(frame, input);
+
+            if self.used.get(&frame).is_some_and(|used| *used != input) {
+                self.rollback_to = Some(self.rollback_to.map_or(frame, |to| to.min(frame)));
+            }
+        }
+
+        while self.remote_inputs.contains_key(&(self.remote_known + 1)) {
+            self.remote_known += 1;
+        }
+
+        self.acked = self.acked.max(ack);
+        self.forget();
+    }
+
+    /// the local inputs the other machine does not have yet, starting from which tick.
+    pub fn outgoing(&self) -> (u64, Vec<FighterInput>) {
+        let inputs = self
+            .local_inputs
+            .range(self.acked + 1..)
+            .map(|(_, input)| *input)
+            .collect();
+
+        (self.acked + 1, inputs)
+    }
+
+    /// the last tick every one of the other machines inputs has arrived for.
+    pub fn ack(&self) -> u64 {
+        self.remote_known
+    }
+
+    /// both fighters inputs (player one first) for every tick made final since this was last
+    /// called, in order.
+    pub fn take_confirmed_inputs(&mut self) -> Vec<(FighterInput, FighterInput)> {
+        std::mem::take(&mut self.confirmed_inputs)
+    }
+
+    /// fixes any wrong guesses, then simulates the next tick with `input` as the local input
+    /// (delayed by the input delay). returns the events of every tick that has become final.
+    /// waits instead, if the simulation is too far ahead of the other machine.
+    pub fn advance(&mut self, bout: &mut Bout, input: FighterInput) -> Vec<BoutEvent> {
+        self.snapshots
+            .entry(self.frame)
+            .or_insert_with(|| bout.clone());
+
+        if let Some(frame) = self.rollback_to.take() {
+            self.roll_back(bout, frame);
+        }
+
+        if self.frame - self.confirmed >= MAX_ROLLBACK {
+            self.stalls += 1;
+        } else {
+            self.local_inputs
+                .entry(self.frame + 1 + self.input_delay)
+                .or_insert(input);
+            self.simulate(bout);
+        }
+
+        self.confirm()
+    }
+
+    /// puts the bout back to how it was before `frame`, and simulates up to where it was.
+    fn roll_back(&mut self, bout: &mut Bout, frame: u64) {
+        let Some(before) = self.snapshots.get(&(frame - 1)) else {
+            return;
+        };
+
+        let to = self.frame;
+        *bout = before.clone();
+        self.frame = frame - 1;
+        self.rollbacks += 1;
+        self.resimulated += to - self.frame;
+
+        while self.frame < to {
+            self.simulate(bout);
+        }
+    }
+
+    fn simulate(&mut self, bout: &mut Bout) {
+        let frame = self.frame + 1;
+        let local = self.local_inputs.get(&frame).copied().unwrap_or_default();
+        let remote = self.predict(frame);
+
+        let events = match self.local {
+            Player::One => bout.step(local, remote),
+            Player::Two => bout.step(remote, local),
+        };
+
+        self.frame = frame;
+        self.used.insert(frame, remote);
+        self.pending.insert(frame, events);
+        self.snapshots.insert(frame, bout.clone());
+    }
+
+    /// the other machines input for `frame`, or a guess that they kept doing the same thing.
+    fn predict(&self, frame: u64) -> FighterInput {
+        self.remote_inputs
+            .range(..=frame)
+            .next_back()
+            .map(|(_, input)| *input)
+            .unwrap_or_default()
+    }
+
+    /// makes final every tick simulated with real inputs. returns their events.
+    fn confirm(&mut self) -> Vec<BoutEvent> {
+        let confirmed = self.remote_known.min(self.frame);
+
+        if self.rollback_to.is_some() || confirmed <= self.confirmed {
+            return Vec::new();
+        }
+
+        for frame in self.confirmed + 1..=confirmed {
+            let local = self.local_inputs.get(&frame).copied().unwrap_or_default();
+            let remote = self.remote_inputs.get(&frame).copied().unwrap_or_default();
+
+            self.confirmed_inputs.push(match self.local {
+                Player::One => (local, remote),
+                Player::Two => (remote, local),
+            });
+        }
+
+        let events = (self.confirmed + 1..=confirmed)
+            .filter_map(|frame| self.pending.remove(&frame))
+            .flatten()
+            .collect();
+        self.confirmed = confirmed;
+        self.forget();
+
+        events
+    }
+
+    /// drops whatever can no longer be rolled back to, or resent.
+    fn forget(&mut self) {
+        let confirmed = self.confirmed;
+
+        self.used.retain(|frame, _| *frame > confirmed);
+        self.snapshots.retain(|frame, _| *frame >= confirmed);
+        // the last real remote input is still needed to guess the next ones.
+        self.remote_inputs.retain(|frame, _| *frame >= confirmed);
+
+        let keep_local = self.acked.min(confirmed);
+        self.local_inputs.retain(|frame, _| *frame > keep_local);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::{offensive, q_learning::ACTIONS, THINK_TICKS},
+        combat::next_screen,
+        fighter::Move,
+        rng::Rng,
+    };
+
+    fn doing(act: Move) -> FighterInput {
+        FighterInput {
+            action: Some(act),
+            ..Default::default()
+        }
+    }
+
+    /// packets on their way from one peer to the other: when they arrive, the first tick of
+    /// their inputs, the inputs, and the ack.
+    type InFlight = Vec<(u64, u64, Vec<FighterInput>, u64)>;
+
+    /// one of the two machines, fencing a scripted (and sometimes surprising) fencer.
+    struct Peer {
+        player: Player,
+        bout: Bout,
+        rollback: Rollback,
+        /// the local input used on every tick.
+        inputs: BTreeMap<u64, FighterInput>,
+        /// how the bout ended, once that is final.
+        ended: Option<BoutEvent>,
+        rng: Rng,
+    }
+
+    impl Peer {
+        fn new(player: Player, input_delay: u64, rng: Rng) -> Self {
+            Self {
+                player,
+                bout: Bout::new(),
+                rollback: Rollback::new(player, input_delay),
+                inputs: (1..=input_delay)
+                    .map(|frame| (frame, FighterInput::default()))
+                    .collect(),
+                ended: None,
+                rng,
+            }
+        }
+
+        fn step(&mut self) {
+            if self.ended.is_some() {
+                return;
+            }
+
+            let input =
+                if !self.bout.tick.is_multiple_of(THINK_TICKS) || !self.bout.can_act(self.player) {
+                    FighterInput::default()
+                } else if self.rng.below(3) == 0 {
+                    ACTIONS[self.rng.below(ACTIONS.len())].into()
+                } else {
+                    offensive(&self.bout, self.player)
+                };
+            let frame = self.rollback.frame();
+            let events = self.rollback.advance(&mut self.bout, input);
+
+            if self.rollback.frame() > frame {
+                self.inputs
+                    .insert(frame + 1 + self.rollback.input_delay(), input);
+            }
+
+            self.ended = events
+                .into_iter()
+                .find(|event| next_screen(*event).is_some());
+        }
+    }
+
+    /// sends the peers unacknowledged inputs over a link that holds them back `latency` ticks
+    /// (plus up to `jitter`), and loses one in `1 / loss`.
+    fn send(from: &Peer, link: &mut InFlight, now: u64, network: (u64, u64, usize), rng: &mut Rng) {
+        let (latency, jitter, loss) = network;
+
+        if rng.below(loss) == 0 {
+            return;
+        }
+
+        let (start, inputs) = from.rollback.outgoing();
+        let arrives = now + latency + rng.below(jitter as usize + 1) as u64;
+        link.push((arrives, start, inputs, from.rollback.ack()));
+    }
+
+    fn deliver(to: &mut Peer, link: &mut InFlight, now: u64) {
+        link.sort_by_key(|(arrives, ..)| *arrives);
+
+        let arrived = link
+            .iter()
+            .take_while(|(arrives, ..)| *arrives <= now)
+            .count();
+
+        for (_, start, inputs, ack) in link.drain(..arrived) {
+            to.rollback.receive(start, &inputs, ack);
+        }
+    }
+
+    /// fences a bout between two peers over a bad network, then fences it again from their
+    /// inputs on one machine. both peers have to end up with exactly that bout. returns how many
+    /// times they rolled back.
+    fn fence_over_network(seed: u64, input_delay: u64, network: (u64, u64, usize)) -> u64 {
+        let mut rng = Rng::new(seed);
+        let mut host = Peer::new(Player::One, input_delay, Rng::new(rng.next_u64()));
+        let mut client = Peer::new(Player::Two, input_delay, Rng::new(rng.next_u64()));
+        let (mut to_host, mut to_client) = (Vec::new(), Vec::new());
+        let mut now = 0;
+
+        while host.ended.is_none() || client.ended.is_none() {
+            assert!(now < 64 * 120, "the bout never ended (seed {seed})");
+
+            deliver(&mut host, &mut to_host, now);
+            deliver(&mut client, &mut to_client, now);
+            host.step();
+            client.step();
+            send(&host, &mut to_client, now, network, &mut rng);
+            send(&client, &mut to_host, now, network, &mut rng);
+            now += 1;
+        }
+
+        let mut reference = Bout::new();
+        let mut ended = None;
+
+        for frame in 1.. {
+            let p1 = host.inputs[&frame];
+            let p2 = client.inputs[&frame];
+
+            ended = reference
+                .step(p1, p2)
+                .into_iter()
+                .find(|event| next_screen(*event).is_some());
+
+            if ended.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(host.ended, ended, "host ended differently (seed {seed})");
+        assert_eq!(
+            client.ended, ended,
+            "client ended differently (seed {seed})"
+        );
+
+        let expected = format!("{reference:?}");
+
+        for peer in [&host, &client] {
+            assert_eq!(
+                peer.rollback
+                    .confirmed_bout()
+                    .map(|bout| format!("{bout:?}")),
+                Some(expected.clone()),
+                "player {:?} desynced (seed {seed})",
+                peer.player
+            );
+        }
+
+        host.rollback.rollbacks + client.rollback.rollbacks
+    }
+
+    #[test]
+    fn lossy_network_stays_in_sync() {
+        let rollbacks: u64 = (1..=6)
+            .map(|seed| fence_over_network(seed, DEFAULT_INPUT_DELAY, (6, 3, 10)))
+            .sum();
+
+        // the network is bad enough that the guesses have to be wrong sometimes.
+        assert!(rollbacks > 0);
+    }
+
+    #[test]
+    fn no_input_delay_stays_in_sync() {
+        for seed in 1..=3 {
+            fence_over_network(seed, 0, (2, 2, 20));
+        }
+    }
+
+    #[test]
+    fn predict_repeats_the_last_input() {
+        let mut rollback = Rollback::new(Player::One, 0);
+        assert_eq!(rollback.predict(1), FighterInput::default());
+
+        rollback.receive(1, &[doing(Move::Advance), doing(Move::Lunge)], 0);
+
+        assert_eq!(rollback.predict(1), doing(Move::Advance));
+        assert_eq!(rollback.predict(2), doing(Move::Lunge));
+        assert_eq!(rollback.predict(9), doing(Move::Lunge));
+    }
+
+    #[test]
+    fn late_input_rolls_back() {
+        let mut rollback = Rollback::new(Player::One, 0);
+        let mut bout = Bout::new();
+
+        for _ in 0..5 {
+            rollback.advance(&mut bout, FighterInput::default());
+        }
+
+        // player two actually advanced on tick 3, not stood still as guessed.
+        let remote = [
+            FighterInput::default(),
+            FighterInput::default(),
+            doing(Move::Advance),
+        ];
+        rollback.receive(1, &remote, 0);
+        rollback.advance(&mut bout, FighterInput::default());
+
+        assert_eq!(rollback.rollbacks, 1);
+        assert_eq!(rollback.resimulated, 3);
+        assert_eq!(rollback.frame(), 6);
+
+        // the guess for the ticks after is that they kept advancing.
+        let mut reference = Bout::new();
+
+        for p2 in remote.into_iter().chain([doing(Move::Advance); 3]) {
+            reference.step(FighterInput::default(), p2);
+        }
+
+        assert_eq!(format!("{bout:?}"), format!("{reference:?}"));
+    }
+
+    #[test]
+    fn waits_once_too_far_ahead() {
+        let mut rollback = Rollback::new(Player::One, 0);
+        let mut bout = Bout::new();
+
+        for _ in 0..MAX_ROLLBACK + 5 {
+            rollback.advance(&mut bout, FighterInput::default());
+        }
+
+        assert_eq!(rollback.frame(), MAX_ROLLBACK);
+        assert_eq!(rollback.stalls, 5);
+        assert_eq!(bout.tick, MAX_ROLLBACK);
+
+        // once the other machine catches up, its ticks are made final, and the bout carries on.
+        rollback.receive(1, &[FighterInput::default(); 4], 0);
+        rollback.advance(&mut bout, FighterInput::default());
+        assert_eq!(rollback.confirmed_bout().map(|bout| bout.tick), Some(4));
+
+        rollback.advance(&mut bout, FighterInput::default());
+        assert_eq!(rollback.frame(), MAX_ROLLBACK + 1);
+    }
+}
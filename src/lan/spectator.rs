@@ -0,0 +1,165 @@
+use super::{protocol::Message, receive, HELLO_INTERVAL, TIMEOUT};
+use crate::{
+    bout::{Bout, BoutEvent},
+    referee::Halt,
+    state::{GameMode, Screen},
+};
+use bevy::prelude::*;
+use std::net::{SocketAddr, UdpSocket};
+
+/// watching the bout hosted by another machine. nothing is simulated here, the host sends the
+/// fighters and score as they stand once each tick is final.
+#[derive(Resource)]
+pub struct SpectatorSession {
+    socket: UdpSocket,
+    host: SocketAddr,
+    /// wether the host has let us watch yet.
+    pub connected: bool,
+    /// seconds since the host was last heard from.
+    silence: f32,
+    /// seconds since the host was last asked to keep sending.
+    since_watch: f32,
+    /// how many bouts the host has started this session.
+    round: u32,
+    /// what ended the current bout, on the host.
+    outcome: Option<BoutEvent>,
+}
+
+impl SpectatorSession {
+    /// starts watching the bout hosted at `host`.
+    pub fn watch(host: SocketAddr) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+        socket.set_nonblocking(true)?;
+
+        let session = Self {
+            socket,
+            host,
+            connected: false,
+            silence: 0.0,
+            since_watch: 0.0,
+            round: 0,
+            outcome: None,
+        };
+        session.send(&Message::Watch);
+
+        Ok(session)
+    }
+
+    pub fn send(&self, message: &Message) {
+        if let Err(e) = self.socket.send_to(&message.encode(), self.host) {
+            warn!("failed to send to {}: {e}", self.host);
+        }
+    }
+}
+
+/// keeps asking the host for snapshots, and gives up if it goes quiet.
+pub fn keep_watching(
+    mut session: ResMut<SpectatorSession>,
+    time: Res<Time>,
+    mut next_state: ResMut<NextState<Screen>>,
+) {
+    session.since_watch += time.delta_seconds();
+
+    if session.since_watch >= HELLO_INTERVAL {
+        session.since_watch = 0.0;
+        session.send(&Message::Watch);
+    }
+
+    if !session.connected {
+        return;
+    }
+
+    session.silence += time.delta_seconds();
+
+    if session.silence >= TIMEOUT {
+        error!("lost the connection to the host");
+        next_state.set(Screen::Welcome);
+    }
+}
+
+/// shows the hosts bout, and follows the host between bouts.
+pub fn spectate(
+    mut session: ResMut<SpectatorSession>,
+    mut bout: ResMut<Bout>,
+    mut game_mode: ResMut<GameMode>,
+    screen: Res<State<Screen>>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut halt: ResMut<Halt>,
+) {
+    let mut snapshots = Vec::new();
+
+    for (message, from) in receive(&session.socket) {
+        if from != session.host {
+            debug!("ignoring {message:?} from {from}");
+            continue;
+        }
+
+        session.silence = 0.0;
+
+        match message {
+            Message::Welcome(..) if !session.connected => {
+                info!("watching the bout hosted by {from}");
+                session.connected = true;
+            }
+            Message::Snapshot(snapshot) => {
+                session.connected = true;
+                snapshots.push(snapshot);
+            }
+            Message::Bye => {
+                info!("the host ended the bout");
+                next_state.set(Screen::Welcome);
+                return;
+            }
+            message => debug!("ignoring {message:?} from {from}"),
+        }
+    }
+
+    let latest = snapshots
+        .into_iter()
+        .max_by_key(|snapshot| (snapshot.round, snapshot.tick));
+
+    let Some(snapshot) = latest else {
+        return;
+    };
+
+    if *game_mode != GameMode::Spectator {
+        // the first snapshot, the bout is under way.
+        *game_mode = GameMode::Spectator;
+        session.round = snapshot.round;
+        bout.state.reset_scores();
+        next_state.set(Screen::NewBout);
+        return;
+    }
+
+    if snapshot.round < session.round
+        || (snapshot.round == session.round && snapshot.tick < bout.tick)
+    {
+        // arrived out of order
+        return;
+    }
+
+    if snapshot.round > session.round {
+        session.round = snapshot.round;
+        session.outcome = None;
+
+        if matches!(*screen.get(), Screen::TouchScored | Screen::Victory) || halt.called() {
+            info!("the host started the next bout");
+            next_state.set(Screen::NewBout);
+            return;
+        }
+    }
+
+    bout.p1 = snapshot.p1;
+    bout.p2 = snapshot.p2;
+    bout.state = snapshot.state;
+    bout.tick = snapshot.tick;
+    bout.over = snapshot.over;
+
+    if *screen.get() == Screen::Game
+        && session.outcome.is_none()
+        && let Some(outcome) = snapshot.outcome
+    {
+        session.outcome = Some(outcome);
+        halt.call(outcome, &bout);
+    }
+}
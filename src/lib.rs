@@ -0,0 +1,64 @@
+#![feature(let_chains)]
+use bevy::prelude::*;
+
+pub mod ai;
+pub mod bout;
+pub mod combat;
+pub mod controls;
+pub mod fighter;
+pub mod lan;
+pub mod lobby;
+pub mod mode_select;
+pub mod paths;
+pub mod pause;
+pub mod player;
+pub mod referee;
+pub mod replay;
+pub mod rng;
+pub mod score_screen;
+pub mod setup;
+pub mod state;
+pub mod tutorial;
+pub mod victory;
+pub mod welcome;
+
+pub const PLAYER_SPEED: f32 = 0.75;
+
+#[derive(Component)]
+pub struct ButtonMarker;
+
+/// the menu buttons the mouse just moved over or clicked, with their text.
+pub type ButtonInteractions<'w, 's> =
+    Query<'w, 's, (&'static Interaction, &'static Children), (Changed<Interaction>, With<Button>)>;
+
+/// what pressing a menu button did.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pressed {
+    /// go to another screen.
+    Go(state::Screen),
+    /// stay, and show this status line.
+    Status(String),
+}
+
+pub fn distance(pos1: f32, pos2: f32) -> f32 {
+    (pos2 - pos1).powf(2.0).sqrt()
+}
+
+/// stops the game clock. bouts are stepped on the fixed ticks of bevys virtual time, so every
+/// fighter (and the computer deciding what they do) stops with it.
+pub fn pause_clock(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+/// starts the game clock again, at full speed.
+pub fn resume_clock(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+    time.set_relative_speed(1.0);
+}
+
+/// despwans menu icons
+pub fn despawn_buttons(mut commands: Commands, buttons_query: Query<Entity, With<ButtonMarker>>) {
+    buttons_query
+        .iter()
+        .for_each(|button| commands.entity(button).despawn_recursive());
+}
@@ -0,0 +1,541 @@
+use crate::{
+    controls::input::MenuInput,
+    despawn_buttons,
+    lan::{
+        discovery::{Discovery, DISCOVERY_PORT},
+        spectator::SpectatorSession,
+        LanSession,
+    },
+    state::Screen,
+    ButtonInteractions, ButtonMarker, Pressed,
+};
+use bevy::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum LobbyButton {
+    /// one of the games found on the LAN, by its place in the list.
+    Game(usize),
+    /// host a game, or join one by typing in its address.
+    Address,
+    Back,
+}
+
+impl LobbyButton {
+    fn next(index: LobbyButton, games: usize) -> Self {
+        match index {
+            Self::Game(i) if i + 1 < games => Self::Game(i + 1),
+            Self::Game(_) => Self::Address,
+            Self::Address => Self::Back,
+            Self::Back if games > 0 => Self::Game(0),
+            Self::Back => Self::Address,
+        }
+    }
+
+    fn prev(index: LobbyButton, games: usize) -> Self {
+        match index {
+            Self::Game(0) => Self::Back,
+            Self::Game(i) => Self::Game(i - 1),
+            Self::Address if games > 0 => Self::Game(games - 1),
+            Self::Address => Self::Back,
+            Self::Back => Self::Address,
+        }
+    }
+}
+
+/// what picking a bout in the lobby does.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LobbyPurpose {
+    /// join the bout as the hosts opponent.
+    Fence,
+    /// watch the bout.
+    Watch,
+}
+
+#[derive(Resource)]
+struct SelectedButton(Option<LobbyButton>, Interaction, LobbyPurpose);
+
+/// holds the rows of found games.
+#[derive(Component)]
+struct GameList;
+
+/// shows how the game in that row is going, and the ping to it.
+#[derive(Component)]
+struct DetailsText(usize);
+
+#[derive(Component)]
+struct StatusText;
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+
+/// lists the bouts waiting for an opponent on the LAN
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LobbyPurpose::Fence)
+            .add_systems(
+                OnEnter(Screen::Lobby),
+                (start_discovery, spawn_buttons).chain(),
+            )
+            .add_systems(OnExit(Screen::Lobby), (despawn_buttons, stop_discovery))
+            .add_systems(Update, keyboard_select.run_if(in_state(Screen::Lobby)))
+            .add_systems(Update, mouse_select.run_if(in_state(Screen::Lobby)))
+            .add_systems(
+                Update,
+                (discover, show_details, button_selection)
+                    .chain()
+                    .run_if(in_state(Screen::Lobby)),
+            );
+    }
+}
+
+fn start_discovery(mut commands: Commands) {
+    match Discovery::listen() {
+        Ok(discovery) => commands.insert_resource(discovery),
+        Err(e) => error!("could not listen for LAN bouts on port {DISCOVERY_PORT}: {e}"),
+    }
+}
+
+fn stop_discovery(mut commands: Commands) {
+    commands.remove_resource::<Discovery>();
+}
+
+/// spawns the buttons (and button text) of the lobby
+fn spawn_buttons(
+    mut commands: Commands,
+    discovery: Option<Res<Discovery>>,
+    purpose: Res<LobbyPurpose>,
+) {
+    debug!("spawning lobby buttons");
+
+    let status = if discovery.is_some() {
+        match *purpose {
+            LobbyPurpose::Fence => "Looking for bouts to join on the LAN...".to_string(),
+            LobbyPurpose::Watch => "Looking for bouts to watch on the LAN...".to_string(),
+        }
+    } else {
+        format!("Could not listen for bouts (is port {DISCOVERY_PORT} in use?)")
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ButtonMarker,
+        ))
+        // status line
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    status,
+                    TextStyle {
+                        // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 16.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                StatusText,
+            ));
+        })
+        // found games, filled in by `discover`
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                GameList,
+            ));
+        })
+        // Host/Join by address button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(40.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Host / Join by Address",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LobbyButton::Address,
+                    ));
+                });
+        })
+        // Back button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Back",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LobbyButton::Back,
+                    ));
+                });
+        });
+
+    commands.insert_resource(SelectedButton(None, Interaction::None, *purpose));
+}
+
+/// listens for hosts, and rebuilds the list of games when one shows up or goes away.
+fn discover(
+    mut commands: Commands,
+    discovery: Option<ResMut<Discovery>>,
+    list_query: Query<Entity, With<GameList>>,
+    mut button_selection: ResMut<SelectedButton>,
+    time: Res<Time>,
+) {
+    let Some(mut discovery) = discovery else {
+        return;
+    };
+
+    if !discovery.update(time.delta_seconds()) {
+        return;
+    }
+
+    let Ok(list) = list_query.get_single() else {
+        return;
+    };
+
+    if let Some(LobbyButton::Game(i)) = button_selection.0
+        && i >= discovery.games.len()
+    {
+        *button_selection = SelectedButton(None, Interaction::None, button_selection.2);
+    }
+
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|parent| {
+        for (i, game) in discovery.games.iter().enumerate() {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(60.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        padding: UiRect::horizontal(Val::Px(8.0)),
+                        justify_content: JustifyContent::SpaceBetween,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("{} - first to {}", game.name, game.touches_to_win),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        LobbyButton::Game(i),
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        DetailsText(i),
+                    ));
+                });
+        }
+    });
+}
+
+fn show_details(
+    discovery: Option<Res<Discovery>>,
+    mut details_query: Query<(&mut Text, &DetailsText)>,
+) {
+    let Some(discovery) = discovery else {
+        return;
+    };
+
+    for (mut text, details) in &mut details_query {
+        let Some(game) = discovery.games.get(details.0) else {
+            continue;
+        };
+
+        let status = if game.fencers < 2 {
+            "waiting"
+        } else {
+            "under way"
+        };
+        let ping = match game.ping {
+            Some(ping) => format!("{} ms", ping.as_millis()),
+            None => "? ms".to_string(),
+        };
+
+        text.sections[0].value = format!("{status}, {} watching - {ping}", game.spectators);
+    }
+}
+
+/// handles using the keyboard to select a button
+fn keyboard_select(
+    menu_input: Res<MenuInput>,
+    mut button_selection: ResMut<SelectedButton>,
+    discovery: Option<Res<Discovery>>,
+) {
+    let games = discovery.map_or(0, |discovery| discovery.games.len());
+
+    if menu_input.back {
+        debug!("pressing back button with keeb");
+        button_selection.0 = Some(LobbyButton::Back);
+        button_selection.1 = Interaction::Pressed;
+        return;
+    }
+
+    match button_selection.0 {
+        Some(button) => {
+            if menu_input.next {
+                debug!("changing button selection to next button with keeb");
+                button_selection.0 = Some(LobbyButton::next(button, games));
+            } else if menu_input.prev {
+                debug!("changing button selection to previous button with keeb");
+                button_selection.0 = Some(LobbyButton::prev(button, games));
+            } else if menu_input.press {
+                debug!("pressing button with keeb");
+                button_selection.1 = Interaction::Pressed;
+            }
+        }
+        None => {
+            if menu_input.prev || menu_input.next {
+                debug!("selecting first button with keeb");
+                button_selection.0 = Some(if games > 0 {
+                    LobbyButton::Game(0)
+                } else {
+                    LobbyButton::Address
+                });
+                button_selection.1 = Interaction::Hovered;
+            }
+        }
+    }
+}
+
+/// handles using the mouse to select a button
+fn mouse_select(
+    mut interaction_query: ButtonInteractions,
+    mut text_query: Query<&LobbyButton, With<Text>>,
+    mut button_selection: ResMut<SelectedButton>,
+) {
+    for (interaction, children) in &mut interaction_query {
+        let Ok(button_type) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        match *interaction {
+            Interaction::Pressed | Interaction::Hovered => {
+                debug!("selecting with mouse");
+                *button_selection =
+                    SelectedButton(Some(*button_type), *interaction, button_selection.2);
+            }
+            Interaction::None => {
+                debug!("unselecting with mouse");
+                *button_selection = SelectedButton(None, *interaction, button_selection.2);
+            }
+        }
+    }
+}
+
+/// handles changing the selected buttons collor and text
+fn button_selection(
+    mut commands: Commands,
+    mut button_query: Query<(&mut BackgroundColor, &mut BorderColor, &Children), With<Button>>,
+    mut text_query: Query<(&mut Text, &LobbyButton)>,
+    mut status_query: Query<&mut Text, (With<StatusText>, Without<LobbyButton>)>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut button_selection: ResMut<SelectedButton>,
+    discovery: Option<Res<Discovery>>,
+) {
+    let select_prefix = "> ";
+
+    for (mut color, mut border_color, children) in &mut button_query {
+        let Ok((mut text, button_type)) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        if Some(*button_type) == button_selection.0 {
+            match button_selection.1 {
+                Interaction::Pressed => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = PRESSED_BUTTON.into();
+
+                    match press_button(
+                        *button_type,
+                        &mut commands,
+                        discovery.as_deref(),
+                        button_selection.2,
+                    ) {
+                        Pressed::Go(screen) => next_state.set(screen),
+                        Pressed::Status(status) => {
+                            if let Ok(mut status_text) = status_query.get_single_mut() {
+                                status_text.sections[0].value = status;
+                            }
+                        }
+                    }
+
+                    button_selection.1 = Interaction::Hovered;
+                }
+                Interaction::Hovered => {
+                    if !text.sections[0].value.starts_with(select_prefix) {
+                        text.sections[0].value =
+                            format!("{select_prefix}{}", text.sections[0].value);
+                    }
+
+                    *color = HOVERED_BUTTON.into();
+                    border_color.0 = Color::WHITE;
+                }
+                Interaction::None => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = NORMAL_BUTTON.into();
+                }
+            }
+        } else {
+            text.sections[0].value = text.sections[0]
+                .value
+                .strip_prefix(select_prefix)
+                .unwrap_or(text.sections[0].value.as_str())
+                .to_string();
+            *color = NORMAL_BUTTON.into();
+            border_color.0 = Color::BLACK;
+        }
+    }
+}
+
+/// called by mouse select or keyboard select. used to envoke the buttons function.
+fn press_button(
+    button: LobbyButton,
+    commands: &mut Commands,
+    discovery: Option<&Discovery>,
+    purpose: LobbyPurpose,
+) -> Pressed {
+    match button {
+        LobbyButton::Game(i) => {
+            let Some(game) = discovery.and_then(|discovery| discovery.games.get(i)) else {
+                return Pressed::Status("That bout is no longer being hosted".to_string());
+            };
+
+            if purpose == LobbyPurpose::Watch {
+                return match SpectatorSession::watch(game.addr) {
+                    Ok(session) => {
+                        // the LAN plugins spectator takes it from here.
+                        info!(
+                            "watching the LAN bout hosted by {} at {}",
+                            game.name, game.addr
+                        );
+                        commands.insert_resource(session);
+                        Pressed::Status(format!("Watching {}, waiting for the bout...", game.name))
+                    }
+                    Err(e) => {
+                        error!("could not open a socket to watch {}: {e}", game.addr);
+                        Pressed::Status(format!("Could not watch: {e}"))
+                    }
+                };
+            }
+
+            if game.fencers >= 2 {
+                return Pressed::Status(format!("{} already has an opponent", game.name));
+            }
+
+            match LanSession::join(game.addr) {
+                Ok(session) => {
+                    // the LAN plugins handshake takes it from here.
+                    info!(
+                        "joining the LAN bout hosted by {} at {}",
+                        game.name, game.addr
+                    );
+                    commands.insert_resource(session);
+                    Pressed::Status(format!("Joining {}...", game.name))
+                }
+                Err(e) => {
+                    error!("could not open a socket to join {}: {e}", game.addr);
+                    Pressed::Status(format!("Could not join: {e}"))
+                }
+            }
+        }
+        LobbyButton::Address => {
+            commands.remove_resource::<LanSession>();
+            commands.remove_resource::<SpectatorSession>();
+            Pressed::Go(Screen::Lan)
+        }
+        LobbyButton::Back => {
+            commands.remove_resource::<LanSession>();
+            commands.remove_resource::<SpectatorSession>();
+            Pressed::Go(Screen::ModeSelect)
+        }
+    }
+}
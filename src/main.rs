@@ -1,36 +1,33 @@
-#![feature(let_chains)]
 use bevy::{app::AppExit, prelude::*, window::PresentMode};
-use fighter::*;
-use state::{GameState, Screen};
-
-mod ai;
-mod combat;
-pub mod fighter;
-mod mode_select;
-mod pause;
-mod player;
-mod score_screen;
-mod setup;
-mod state;
-mod welcome;
-
-pub const PLAYER_SPEED: f32 = 0.75;
-
-#[derive(Component)]
-pub struct ButtonMarker;
+use fencing_game::{
+    ai, bout,
+    bout::Bout,
+    combat, controls, lan, lobby, mode_select, pause, player, referee, replay, score_screen, setup,
+    state::{GameMode, Screen},
+    tutorial, victory, welcome,
+};
 
 fn main() {
     App::new()
-        .insert_resource(GameState::new())
+        .insert_resource(Bout::new())
+        .insert_resource(GameMode::VsComputer)
+        .insert_resource(Time::<Fixed>::from_seconds(bout::TICK.into()))
         .init_state::<Screen>()
         .add_plugins(setup::SetupPlugin)
         .add_plugins(player::PlayerPlugin)
         .add_plugins(ai::AiPlugin)
         .add_plugins(combat::CombatPlugin)
+        .add_plugins(referee::RefereePlugin)
         .add_plugins(score_screen::ScoreScreenPlugin)
+        .add_plugins(victory::VictoryScreenPlugin)
+        .add_plugins(tutorial::TutorialPlugin)
         .add_plugins(pause::PauseScreenPlugin)
         .add_plugins(welcome::WelcomeScreenPlugin)
+        .add_plugins(controls::ControlsPlugin)
         .add_plugins(mode_select::ModeScreenPlugin)
+        .add_plugins(lobby::LobbyPlugin)
+        .add_plugins(lan::LanPlugin)
+        .add_plugins(replay::ReplayPlugin)
         .add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
@@ -59,17 +56,6 @@ fn main() {
         .run()
 }
 
-pub fn distance(pos1: f32, pos2: f32) -> f32 {
-    (pos2 - pos1).powf(2.0).sqrt()
-}
-
 fn exit_game(mut exit: EventWriter<AppExit>) {
     exit.send(AppExit);
 }
-
-/// despwans menu icons
-pub fn despawn_buttons(mut commands: Commands, buttons_query: Query<Entity, With<ButtonMarker>>) {
-    buttons_query
-        .iter()
-        .for_each(|button| commands.entity(button).despawn_recursive());
-}
@@ -1,12 +1,31 @@
-use std::ops::Not;
-
-use crate::{despawn_buttons, state::Screen, ButtonMarker};
-use bevy::prelude::*;
+use crate::{
+    ai::{
+        q_learning::{QAgent, QMode},
+        save_q_agent, ComputerAi,
+    },
+    controls::input::MenuInput,
+    despawn_buttons,
+    fighter::{Handed, Player},
+    lobby::LobbyPurpose,
+    referee::Countdown,
+    state::{GameMode, Handedness, MatchRules, Screen},
+    ButtonInteractions, ButtonMarker,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 #[derive(Component, Clone, Copy, PartialEq, Eq)]
 enum ModeSelMenuButton {
     Comp,
     CompAi,
+    Learning,
+    ResetAi,
+    Local,
+    P1Hand,
+    P2Hand,
+    Touches,
+    Clock,
+    Break,
+    Countdown,
     Lan,
     Spectate,
 }
@@ -15,7 +34,16 @@ impl ModeSelMenuButton {
     fn next(index: ModeSelMenuButton) -> Self {
         match index {
             Self::Comp => Self::CompAi,
-            Self::CompAi => Self::Lan,
+            Self::CompAi => Self::Learning,
+            Self::Learning => Self::ResetAi,
+            Self::ResetAi => Self::Local,
+            Self::Local => Self::P1Hand,
+            Self::P1Hand => Self::P2Hand,
+            Self::P2Hand => Self::Touches,
+            Self::Touches => Self::Clock,
+            Self::Clock => Self::Break,
+            Self::Break => Self::Countdown,
+            Self::Countdown => Self::Lan,
             Self::Lan => Self::Spectate,
             Self::Spectate => Self::Comp,
         }
@@ -25,32 +53,90 @@ impl ModeSelMenuButton {
         match index {
             Self::Comp => Self::Spectate,
             Self::CompAi => Self::Comp,
-            Self::Lan => Self::CompAi,
+            Self::Learning => Self::CompAi,
+            Self::ResetAi => Self::Learning,
+            Self::Local => Self::ResetAi,
+            Self::P1Hand => Self::Local,
+            Self::P2Hand => Self::P1Hand,
+            Self::Touches => Self::P2Hand,
+            Self::Clock => Self::Touches,
+            Self::Break => Self::Clock,
+            Self::Countdown => Self::Break,
+            Self::Lan => Self::Countdown,
             Self::Spectate => Self::Lan,
         }
     }
 }
 
-#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
-enum ComputerAi {
-    Offensive,
-    QLearning,
-}
+#[derive(Resource)]
+struct SelectedButton(Option<ModeSelMenuButton>, Interaction);
 
-impl Not for ComputerAi {
-    type Output = ComputerAi;
+/// the settings picked on this screen, changed in place as their buttons are pressed.
+#[derive(SystemParam)]
+struct Settings<'w> {
+    computer_ai: ResMut<'w, ComputerAi>,
+    q_agent: ResMut<'w, QAgent>,
+    handedness: ResMut<'w, Handedness>,
+    rules: ResMut<'w, MatchRules>,
+    countdown: ResMut<'w, Countdown>,
+}
 
-    fn not(self) -> Self::Output {
-        match self {
-            Self::Offensive => Self::QLearning,
-            Self::QLearning => Self::Offensive,
+impl Settings<'_> {
+    /// changes the setting behind `button`, returns the buttons new text. None if the button is
+    /// not a setting.
+    fn press(&mut self, button: ModeSelMenuButton) -> Option<String> {
+        match button {
+            ModeSelMenuButton::CompAi => {
+                *self.computer_ai = !*self.computer_ai;
+                Some(format!("{:?}", *self.computer_ai))
+            }
+            ModeSelMenuButton::Learning => {
+                self.q_agent.mode = match self.q_agent.mode {
+                    QMode::Training => QMode::Inference,
+                    QMode::Inference => QMode::Training,
+                };
+                Some(learning_label(self.q_agent.mode))
+            }
+            ModeSelMenuButton::ResetAi => {
+                info!("resetting the q-learning computer players brain");
+                self.q_agent.reset();
+                // a reset brain has to learn again, or it would only stand there.
+                self.q_agent.mode = QMode::Training;
+                save_q_agent(&self.q_agent);
+                Some("Reset AI Brain".to_string())
+            }
+            ModeSelMenuButton::P1Hand => {
+                self.handedness.p1 = !self.handedness.p1;
+                Some(hand_label(Player::One, self.handedness.p1))
+            }
+            ModeSelMenuButton::P2Hand => {
+                self.handedness.p2 = !self.handedness.p2;
+                Some(hand_label(Player::Two, self.handedness.p2))
+            }
+            ModeSelMenuButton::Touches => {
+                *self.rules = self.rules.next_touches();
+                Some(touches_label(*self.rules))
+            }
+            ModeSelMenuButton::Clock => {
+                *self.rules = self.rules.next_clock();
+                Some(clock_label(*self.rules))
+            }
+            ModeSelMenuButton::Break => {
+                *self.rules = self.rules.next_break();
+                Some(break_label(*self.rules))
+            }
+            ModeSelMenuButton::Countdown => {
+                *self.countdown = self.countdown.next();
+                Some(countdown_label(*self.countdown))
+            }
+            ModeSelMenuButton::Comp
+            | ModeSelMenuButton::Local
+            | ModeSelMenuButton::Lan
+            | ModeSelMenuButton::Spectate => None,
         }
     }
 }
 
-#[derive(Resource)]
-struct SelectedButton(Option<ModeSelMenuButton>, Interaction, ComputerAi);
-
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
@@ -67,12 +153,26 @@ impl Plugin for ModeScreenPlugin {
             .add_systems(
                 Update,
                 button_selection.run_if(in_state(Screen::ModeSelect)),
+            )
+            .add_systems(
+                Update,
+                show_learning
+                    .after(button_selection)
+                    .run_if(in_state(Screen::ModeSelect))
+                    .run_if(resource_changed::<QAgent>),
             );
     }
 }
 
 /// spawns the buttons (and button text) of the score menu
-fn spawn_buttons(mut commands: Commands) {
+fn spawn_buttons(
+    mut commands: Commands,
+    computer_ai: Res<ComputerAi>,
+    q_agent: Res<QAgent>,
+    handedness: Res<Handedness>,
+    rules: Res<MatchRules>,
+    countdown: Res<Countdown>,
+) {
     debug!("spawning Mode Select menu buttons");
 
     commands
@@ -143,7 +243,7 @@ fn spawn_buttons(mut commands: Commands) {
                 .with_children(|parent| {
                     parent.spawn((
                         TextBundle::from_section(
-                            format!("{:?}", ComputerAi::Offensive),
+                            format!("{:?}", *computer_ai),
                             TextStyle {
                                 // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                                 font_size: 16.0,
@@ -155,6 +255,303 @@ fn spawn_buttons(mut commands: Commands) {
                     ));
                 });
         })
+        // wether the q-learning computer player keeps learning while it fences
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            learning_label(q_agent.mode),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::Learning,
+                    ));
+                });
+        })
+        // forget what the q-learning computer player has learned
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Reset AI Brain",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::ResetAi,
+                    ));
+                });
+        })
+        // two people fencing on this machine
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Vs. Human (Local)",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::Local,
+                    ));
+                });
+        })
+        // which hand player one fences with
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            hand_label(Player::One, handedness.p1),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::P1Hand,
+                    ));
+                });
+        })
+        // which hand player two fences with
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            hand_label(Player::Two, handedness.p2),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::P2Hand,
+                    ));
+                });
+        })
+        // how many touches the match is fenced to
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            touches_label(*rules),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::Touches,
+                    ));
+                });
+        })
+        // the clock the match is fenced with
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            clock_label(*rules),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::Clock,
+                    ));
+                });
+        })
+        // how long the break between periods lasts
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            break_label(*rules),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::Break,
+                    ));
+                });
+        })
+        // how long the referee counts down before each bout
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            countdown_label(*countdown),
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        ModeSelMenuButton::Countdown,
+                    ));
+                });
+        })
         // Controls menu button
         .with_children(|parent| {
             parent
@@ -222,38 +619,92 @@ fn spawn_buttons(mut commands: Commands) {
                 });
         });
 
-    commands.insert_resource(SelectedButton(
-        None,
-        Interaction::None,
-        ComputerAi::Offensive,
-    ));
+    commands.insert_resource(SelectedButton(None, Interaction::None));
+}
+
+/// the text of the button that picks which hand `player` fences with.
+fn hand_label(player: Player, handed: Handed) -> String {
+    let player = match player {
+        Player::One => "P1",
+        Player::Two => "P2",
+    };
+
+    format!("{player}: {handed:?} Handed")
+}
+
+/// the text of the button that picks wether the q-learning computer player keeps learning.
+fn learning_label(mode: QMode) -> String {
+    match mode {
+        QMode::Training => "Learning: On".to_string(),
+        QMode::Inference => "Learning: Off".to_string(),
+    }
+}
+
+/// the text of the button that picks how many touches the match is fenced to.
+fn touches_label(rules: MatchRules) -> String {
+    format!("Touches: {}", rules.touches_to_win)
+}
+
+/// the text of the button that picks how long the referee counts down before each bout.
+fn countdown_label(countdown: Countdown) -> String {
+    match countdown.0 {
+        0 => "Countdown: Off".to_string(),
+        seconds => format!("Countdown: {seconds}s"),
+    }
+}
+
+/// the text of the button that picks the clock the match is fenced with.
+fn clock_label(rules: MatchRules) -> String {
+    match (rules.period, rules.periods) {
+        (None, _) => "Time: Off".to_string(),
+        (Some(period), 1) => format!("Time: {}:{:02}", period / 60, period % 60),
+        (Some(period), periods) => {
+            format!("Time: {periods} x {}:{:02}", period / 60, period % 60)
+        }
+    }
+}
+
+/// the text of the button that picks how long the break between periods lasts.
+fn break_label(rules: MatchRules) -> String {
+    format!(
+        "Break: {}:{:02}",
+        rules.break_time / 60,
+        rules.break_time % 60
+    )
+}
+
+/// keeps the learning button up to date, resetting the brain turns learning on too.
+fn show_learning(q_agent: Res<QAgent>, mut text_query: Query<(&mut Text, &ModeSelMenuButton)>) {
+    for (mut text, button) in &mut text_query {
+        if *button == ModeSelMenuButton::Learning {
+            let selected = text.sections[0].value.starts_with("> ");
+            let label = learning_label(q_agent.mode);
+            text.sections[0].value = if selected {
+                format!("> {label}")
+            } else {
+                label
+            };
+        }
+    }
 }
 
 /// handles using the keyboard to select a button
-fn keyboard_select(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut button_selection: ResMut<SelectedButton>,
-) {
+fn keyboard_select(menu_input: Res<MenuInput>, mut button_selection: ResMut<SelectedButton>) {
     match button_selection.0 {
         Some(button) => {
-            if keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.next {
                 debug!("changing button selection to next button with keeb");
                 button_selection.0 = Some(ModeSelMenuButton::next(button));
-            } else if keyboard_input.just_released(KeyCode::ArrowUp) {
+            } else if menu_input.prev {
                 debug!("changing button selection to previous button with keeb");
                 button_selection.0 = Some(ModeSelMenuButton::prev(button));
-            } else if keyboard_input.just_released(KeyCode::Enter) {
+            } else if menu_input.press {
                 debug!("pressing button with keeb");
                 button_selection.1 = Interaction::Pressed;
             }
         }
         None => {
-            if keyboard_input.just_released(KeyCode::ArrowUp)
-                || keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.prev || menu_input.next {
                 debug!("selecting continue button with keeb");
                 button_selection.0 = Some(ModeSelMenuButton::Comp);
                 button_selection.1 = Interaction::Hovered;
@@ -264,7 +715,7 @@ fn keyboard_select(
 
 /// handles using the mouse to select a button
 fn mouse_select(
-    mut interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: ButtonInteractions,
     mut text_query: Query<&ModeSelMenuButton, With<Text>>,
     mut button_selection: ResMut<SelectedButton>,
 ) {
@@ -276,12 +727,11 @@ fn mouse_select(
         match *interaction {
             Interaction::Pressed | Interaction::Hovered => {
                 debug!("selecting with mouse");
-                *button_selection =
-                    SelectedButton(Some(button_type.clone()), *interaction, button_selection.2);
+                *button_selection = SelectedButton(Some(*button_type), *interaction);
             }
             Interaction::None => {
                 debug!("unselecting with mouse");
-                *button_selection = SelectedButton(None, *interaction, button_selection.2);
+                *button_selection = SelectedButton(None, *interaction);
             }
         }
     }
@@ -293,6 +743,8 @@ fn button_selection(
     mut text_query: Query<(&mut Text, &ModeSelMenuButton)>,
     mut next_state: ResMut<NextState<Screen>>,
     mut button_selection: ResMut<SelectedButton>,
+    mut settings: Settings,
+    mut commands: Commands,
 ) {
     let select_prefix = "> ";
 
@@ -310,10 +762,21 @@ fn button_selection(
                         .unwrap_or(text.sections[0].value.as_str())
                         .to_string();
                     *color = PRESSED_BUTTON.into();
-                    if *button_type == ModeSelMenuButton::CompAi {
-                        text.sections[0].value = format!("{:?}", !button_selection.2);
-                        button_selection.2 = !button_selection.2;
+                    if let Some(label) = settings.press(*button_type) {
+                        text.sections[0].value = label;
                     } else {
+                        match *button_type {
+                            ModeSelMenuButton::Comp => {
+                                commands.insert_resource(GameMode::VsComputer)
+                            }
+                            ModeSelMenuButton::Local => commands.insert_resource(GameMode::VsHuman),
+                            ModeSelMenuButton::Lan => commands.insert_resource(LobbyPurpose::Fence),
+                            ModeSelMenuButton::Spectate => {
+                                commands.insert_resource(LobbyPurpose::Watch)
+                            }
+                            _ => {}
+                        }
+
                         next_state.set(press_button(button_type.clone()));
                     }
                     button_selection.1 = Interaction::Hovered;
@@ -351,7 +814,8 @@ fn button_selection(
 /// called by mouse select or keyboard select. used to envoke the buttons function
 fn press_button(button: ModeSelMenuButton) -> Screen {
     match button {
-        ModeSelMenuButton::Comp => Screen::NewBout,
+        ModeSelMenuButton::Comp | ModeSelMenuButton::Local => Screen::NewBout,
+        ModeSelMenuButton::Lan | ModeSelMenuButton::Spectate => Screen::Lobby,
         // ModeSelMenuButton::Controls => {
         //     error!("controls menu has yet to programmed");
         //     Screen::Welcome
@@ -0,0 +1,34 @@
+use std::{env, path::PathBuf};
+
+const APP_DIR: &str = "fencing-game";
+
+/// the directory to keep files the game generates (like the computer players brain) in.
+/// follows `$XDG_DATA_HOME` on linux, `Application Support` on mac, and `%APPDATA%` on windows.
+/// falls back to the current directory if none of those can be found.
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+
+    base.unwrap_or_default().join(APP_DIR)
+}
+
+/// the directory to keep the players settings (like their controls) in. follows
+/// `$XDG_CONFIG_HOME` on linux, and is the same as `data_dir` everywhere else.
+pub fn config_dir() -> PathBuf {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return data_dir();
+    }
+
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join(APP_DIR)
+}
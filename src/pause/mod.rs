@@ -1,7 +1,14 @@
 use crate::{
+    bout::Bout,
+    controls::{
+        bindings::InputAction,
+        input::{ActionInput, MenuInput},
+    },
     despawn_buttons,
-    state::{GameState, Screen},
-    ButtonMarker,
+    fighter::Controller,
+    pause_clock, resume_clock,
+    state::{local_bout, Screen},
+    ButtonInteractions, ButtonMarker,
 };
 use bevy::{prelude::*, window::PrimaryWindow};
 
@@ -42,12 +49,19 @@ pub struct PauseScreenPlugin;
 
 impl Plugin for PauseScreenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(Screen::PauseMenu), spawn_buttons)
+        app.add_systems(OnEnter(Screen::PauseMenu), (spawn_buttons, pause_clock))
             .add_systems(OnExit(Screen::PauseMenu), despawn_buttons)
+            // the clock stays stopped while the controls are looked at from the pause menu, it
+            // only starts again once the bout does (or is quit).
+            .add_systems(OnEnter(Screen::Game), resume_clock)
+            .add_systems(OnEnter(Screen::Welcome), resume_clock)
             .add_systems(Update, keyboard_select.run_if(in_state(Screen::PauseMenu)))
             .add_systems(Update, mouse_select.run_if(in_state(Screen::PauseMenu)))
             .add_systems(Update, button_selection.run_if(in_state(Screen::PauseMenu)))
-            .add_systems(Update, pause_game.run_if(in_state(Screen::Game)))
+            .add_systems(
+                Update,
+                pause_game.run_if(in_state(Screen::Game)).run_if(local_bout),
+            )
             .add_systems(Update, unpause_game.run_if(in_state(Screen::PauseMenu)));
     }
 }
@@ -56,7 +70,7 @@ impl Plugin for PauseScreenPlugin {
 fn spawn_buttons(
     mut commands: Commands,
     window_query: Query<&Window, With<PrimaryWindow>>,
-    world_state: Res<GameState>,
+    bout: Res<Bout>,
 ) {
     debug!("spawning pause menu buttons");
 
@@ -104,7 +118,7 @@ fn spawn_buttons(
                 })
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        format!("Touches => {:>3}", world_state.p1_score.touches),
+                        format!("Touches => {:>3}", bout.state.p1_score.touches),
                         TextStyle {
                             // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                             font_size: 32.0,
@@ -115,7 +129,7 @@ fn spawn_buttons(
                 })
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        format!("Matches => {:>3}", world_state.p1_score.matches),
+                        format!("Matches => {:>3}", bout.state.p1_score.matches),
                         TextStyle {
                             // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                             font_size: 32.0,
@@ -268,7 +282,7 @@ fn spawn_buttons(
                 })
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        format!("{:<3} <= Touches", world_state.p1_score.touches),
+                        format!("{:<3} <= Touches", bout.state.p1_score.touches),
                         TextStyle {
                             // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                             font_size: 32.0,
@@ -279,7 +293,7 @@ fn spawn_buttons(
                 })
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        format!("{:<3} <= Matches", world_state.p1_score.matches),
+                        format!("{:<3} <= Matches", bout.state.p1_score.matches),
                         TextStyle {
                             // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
                             font_size: 32.0,
@@ -294,30 +308,22 @@ fn spawn_buttons(
 }
 
 /// handles using the keyboard to select a button
-fn keyboard_select(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut button_selection: ResMut<SelectedButton>,
-) {
+fn keyboard_select(menu_input: Res<MenuInput>, mut button_selection: ResMut<SelectedButton>) {
     match button_selection.0 {
         Some(button) => {
-            if keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.next {
                 debug!("changing button selection to next button with keeb");
                 button_selection.0 = Some(PauseMenuButton::next(button));
-            } else if keyboard_input.just_released(KeyCode::ArrowUp) {
+            } else if menu_input.prev {
                 debug!("changing button selection to previous button with keeb");
                 button_selection.0 = Some(PauseMenuButton::prev(button));
-            } else if keyboard_input.just_released(KeyCode::Enter) {
+            } else if menu_input.press {
                 debug!("pressing button with keeb");
                 button_selection.1 = Interaction::Pressed;
             }
         }
         None => {
-            if keyboard_input.just_released(KeyCode::ArrowUp)
-                || keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.prev || menu_input.next {
                 debug!("selecting continue button with keeb");
                 button_selection.0 = Some(PauseMenuButton::Continue);
                 button_selection.1 = Interaction::Hovered;
@@ -328,7 +334,7 @@ fn keyboard_select(
 
 /// handles using the mouse to select a button
 fn mouse_select(
-    mut interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: ButtonInteractions,
     mut text_query: Query<&PauseMenuButton, With<Text>>,
     mut button_selection: ResMut<SelectedButton>,
 ) {
@@ -406,20 +412,20 @@ fn button_selection(
 fn press_button(button: PauseMenuButton) -> Screen {
     match button {
         PauseMenuButton::Continue => Screen::Game,
-        PauseMenuButton::Controls => {
-            error!("controls menu has yet to programmed");
-            Screen::PauseMenu
-        }
+        PauseMenuButton::Controls => Screen::Controls,
         PauseMenuButton::RageQuit => Screen::Welcome,
     }
 }
 
 /// handles pausing the game
 fn pause_game(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_input: ActionInput,
+    bout: Res<Bout>,
     mut next_state: ResMut<NextState<Screen>>,
 ) {
-    if keyboard_input.just_released(KeyCode::Escape) {
+    let seats = bout.players(Controller::Player).len();
+
+    if (0..seats).any(|seat| action_input.just_released(seat, InputAction::Pause)) {
         info!("pausing the game");
         next_state.set(Screen::PauseMenu);
     }
@@ -427,10 +433,16 @@ fn pause_game(
 
 /// handles unpausing the game
 fn unpause_game(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_input: ActionInput,
+    menu_input: Res<MenuInput>,
+    bout: Res<Bout>,
     mut next_state: ResMut<NextState<Screen>>,
 ) {
-    if keyboard_input.just_released(KeyCode::Escape) {
+    let seats = bout.players(Controller::Player).len();
+
+    if (0..seats).any(|seat| action_input.just_released(seat, InputAction::Pause))
+        || menu_input.back
+    {
         info!("resuming the game");
         next_state.set(Screen::Game);
     }
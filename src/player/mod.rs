@@ -1,25 +1,105 @@
 use crate::{
-    combat::GaurdIconMarker,
+    bout::{Bout, TICK},
+    combat::{step_bout, BoutInputs, GaurdIconMarker},
+    controls::{bindings::InputAction, input::ActionInput},
     fighter::*,
-    state::{GameState, Screen},
+    state::{GameMode, Screen},
 };
 use bevy::prelude::*;
+use std::collections::HashMap;
 
 #[derive(Component)]
 pub struct PlayerMarker;
 
-#[derive(Resource)]
-pub struct P2Timer(pub Time);
+/// a key or button that can be double tapped.
+#[derive(Debug, Default)]
+struct Tap {
+    /// wether it was held down last tick.
+    held: bool,
+    /// the tick it was last pressed on, if that press could still be doubled.
+    pressed_on: Option<u64>,
+}
+
+/// keeps track of key presses for each seat, to spot double taps of advance and retreat, and
+/// presses of the stance key.
+#[derive(Resource, Debug, Default)]
+pub struct Taps(HashMap<(usize, InputAction), Tap>);
+
+impl Taps {
+    /// updates the action at `seat` with wether it is `held`. returns wether it was just
+    /// pressed.
+    fn pressed(&mut self, seat: usize, action: InputAction, held: bool) -> bool {
+        let tap = self.0.entry((seat, action)).or_default();
+        let pressed = held && !tap.held;
+
+        tap.held = held;
+
+        pressed
+    }
+
+    /// updates the action at `seat` with wether it is `held` on `tick`. returns wether it was
+    /// just pressed for the second time within `window` ticks.
+    fn double_tapped(
+        &mut self,
+        seat: usize,
+        action: InputAction,
+        held: bool,
+        tick: u64,
+        window: u64,
+    ) -> bool {
+        let tap = self.0.entry((seat, action)).or_default();
+        let pressed = held && !tap.held;
+        let doubled = pressed
+            && tap
+                .pressed_on
+                .is_some_and(|on| on <= tick && tick - on <= window);
+
+        tap.held = held;
+
+        if pressed {
+            // a third tap starts over instead of doubling again.
+            tap.pressed_on = (!doubled).then_some(tick);
+        }
+
+        doubled
+    }
+}
+
+/// the stance each seat wants to fence in, switched with the stance key. it is sent every tick,
+/// so a switch pressed mid lunge happens once the lunge is done.
+#[derive(Resource, Debug, Default)]
+pub struct Stances(HashMap<usize, Stance>);
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnExit(Screen::NewBout), spawn_fighter_one)
-            .add_systems(OnExit(Screen::NewBout), spawn_p1_gaurd_icon)
-            .add_systems(Update, player_movement.run_if(in_state(Screen::Game)))
-            .add_systems(Update, reset_player.run_if(in_state(Screen::Game)))
-            .add_systems(Update, player_blade_play.run_if(in_state(Screen::Game)));
+        app.init_resource::<Taps>()
+            .init_resource::<Stances>()
+            .add_systems(OnEnter(Screen::NewBout), spawn_fighter_one)
+            .add_systems(OnEnter(Screen::NewBout), spawn_p1_gaurd_icon)
+            .add_systems(
+                OnEnter(Screen::Replay),
+                (spawn_fighter_one, spawn_p1_gaurd_icon),
+            )
+            .add_systems(
+                OnEnter(Screen::HowToPlay),
+                (spawn_fighter_one, spawn_p1_gaurd_icon),
+            )
+            .add_systems(
+                FixedUpdate,
+                player_movement
+                    .before(step_bout)
+                    .run_if(in_state(Screen::Game).or_else(in_state(Screen::HowToPlay)))
+                    .run_if(not(resource_equals(GameMode::Spectator))),
+            )
+            .add_systems(
+                FixedUpdate,
+                player_blade_play
+                    .before(step_bout)
+                    .run_if(in_state(Screen::Game).or_else(in_state(Screen::HowToPlay)))
+                    .run_if(not(resource_equals(GameMode::Spectator))),
+            );
     }
 }
 
@@ -29,21 +109,10 @@ fn spawn_fighter_one(
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
     let texture = asset_server.load("sprites/fighter-sprites.png");
-    let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 2, 1, None, None);
+    let layout = TextureAtlasLayout::from_grid(Vec2::new(32.0, 32.0), 8, 1, None, None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
 
     commands.spawn((
-        Fighter {
-            gaurd: Gaurd::Left,
-            position: -2.0,
-            stance: Stance::Offence,
-            handed: Handed::Right,
-            player: Player::One,
-            // contoller: Controller::Player,
-            parrying: false,
-            action: Action::from(Move::EnGarde),
-            crouched: false,
-        },
         SpriteSheetBundle {
             texture,
             atlas: TextureAtlas {
@@ -84,97 +153,98 @@ fn spawn_p1_gaurd_icon(
     ));
 }
 
+/// the players controlled by someone at this machine, with the seat each one reads their input
+/// from.
+fn seats(bout: &Bout) -> impl Iterator<Item = (usize, Player)> {
+    bout.players(Controller::Player).into_iter().enumerate()
+}
+
+/// reads the footwork keys and buttons into the input for the next tick of every fighter
+/// controlled from this machine. pressing advance or retreat twice quickly doubles the step,
+/// and the stance key switches between offence and defence.
 pub fn player_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<(&mut Fighter, &mut TextureAtlas), With<PlayerMarker>>,
-    time: Res<Time>,
-    mut world_state: ResMut<GameState>,
+    action_input: ActionInput,
+    bout: Res<Bout>,
+    mut inputs: ResMut<BoutInputs>,
+    mut taps: ResMut<Taps>,
+    mut stances: ResMut<Stances>,
 ) {
-    if let Ok((mut player, mut atlas)) = player_query.get_single_mut() {
-        if !player.action.blocked() {
-            if keyboard_input.pressed(KeyCode::KeyA) {
-                // retreat
-                player.set_action(Move::Retreat);
-
-                if world_state.row == Some(Player::One) {
-                    world_state.row = None;
-                }
-            } else if keyboard_input.pressed(KeyCode::KeyD) {
-                // advance
-                player.set_action(Move::Advance);
-
-                if world_state.row.is_none() {
-                    world_state.row = Some(Player::One);
-                }
-            } else if keyboard_input.pressed(KeyCode::KeyW) {
-                // lunge
-                player.set_action(Move::Lunge);
-                atlas.index = 1;
-                world_state.lunge(Player::One);
-            } else if keyboard_input.pressed(KeyCode::KeyS) {
-                // crouch
-                error!("Crouching not yet implemented yet");
-            }
+    for (seat, player) in seats(&bout) {
+        // player two fences from the right hand side, so backwards is the other way.
+        let (retreat, advance) = match player {
+            Player::One => (InputAction::Retreat, InputAction::Advance),
+            Player::Two => (InputAction::Advance, InputAction::Retreat),
+        };
+        let window = (action_input.double_tap(seat) / TICK) as u64;
+        let retreating = action_input.pressed(seat, retreat);
+        let advancing = action_input.pressed(seat, advance);
+        let double_retreat = taps.double_tapped(seat, retreat, retreating, bout.tick, window);
+        let double_advance = taps.double_tapped(seat, advance, advancing, bout.tick, window);
+        let switch = taps.pressed(
+            seat,
+            InputAction::Stance,
+            action_input.pressed(seat, InputAction::Stance),
+        );
+        let stance = stances
+            .0
+            .entry(seat)
+            .or_insert(bout.fighter(player).en_garde);
+
+        if switch {
+            *stance = match stance {
+                Stance::Offence => Stance::Defence,
+                _ => Stance::Offence,
+            };
         }
 
-        let pos_d = player.update_movement(time.clone());
-
-        player.position += pos_d;
-    }
-}
-
-fn reset_player(mut player_query: Query<(&Fighter, &mut TextureAtlas), With<PlayerMarker>>) {
-    if let Ok((player, mut atlas)) = player_query.get_single_mut()
-        && player.action.act != Move::Lunge
-        && atlas.index > 0
-    {
-        debug!("resetting player sprite to standing position");
-        atlas.index = 0;
+        let input = inputs.get_mut(player);
+
+        input.crouch = action_input.pressed(seat, InputAction::Crouch);
+        input.stance = Some(*stance);
+        input.action = if double_retreat {
+            Some(Move::DoubleRetreat)
+        } else if retreating {
+            // retreat
+            Some(Move::Retreat)
+        } else if double_advance {
+            Some(Move::DoubleAdvance)
+        } else if advancing {
+            // advance
+            Some(Move::Advance)
+        } else if action_input.pressed(seat, InputAction::Lunge) {
+            // lunge
+            Some(Move::Lunge)
+        } else if action_input.pressed(seat, InputAction::Fleche) {
+            Some(Move::Fleche)
+        } else if action_input.pressed(seat, InputAction::Balestra) {
+            Some(Move::Balestra)
+        } else if action_input.pressed(seat, InputAction::Beat) {
+            // beat
+            Some(Move::Beat)
+        } else {
+            None
+        };
     }
 }
 
+/// reads the gaurd keys and buttons into the input for the next tick of every fighter controlled
+/// from this machine.
 pub fn player_blade_play(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<&mut Fighter, With<PlayerMarker>>,
-    player2_query: Query<&Fighter, Without<PlayerMarker>>,
-    _time: Res<Time>,
-    mut world_state: ResMut<GameState>,
+    action_input: ActionInput,
+    bout: Res<Bout>,
+    mut inputs: ResMut<BoutInputs>,
 ) {
-    if let (Ok(mut player), Ok(player2)) =
-        (player_query.get_single_mut(), player2_query.get_single())
-    {
-        if !player.action.blocked() {
-            let prev_gaurd = player.gaurd.clone();
-
-            if keyboard_input.pressed(KeyCode::ArrowLeft) {
-                player.gaurd = Gaurd::Left;
-            } else if keyboard_input.pressed(KeyCode::ArrowRight) {
-                player.gaurd = Gaurd::Right;
-            } else if keyboard_input.pressed(KeyCode::ArrowUp) {
-                player.gaurd = Gaurd::Up;
-            } else if keyboard_input.pressed(KeyCode::ArrowDown) {
-                player.gaurd = Gaurd::Down;
-            }
-
-            if prev_gaurd != player.gaurd {
-                debug!("player 1 gaurd change: {:?}", player.gaurd);
-            }
-
-            if prev_gaurd != player.gaurd && player2.lunged() && player.gaurd.parries(player2.gaurd)
-            {
-                player.parrying = true;
-            } else if !(player2.lunged() && player.gaurd.parries(player2.gaurd)) {
-                player.parrying = false;
-            }
-
-            if Some(Player::Two) == world_state.lunger
-                && prev_gaurd != player.gaurd
-                && player.gaurd.parries(player2.gaurd)
-            {
-                info!("player 1 parried and stole right of way");
-                world_state.lunger = None;
-                world_state.row = Some(Player::One);
-            }
-        }
+    for (seat, player) in seats(&bout) {
+        inputs.get_mut(player).gaurd = if action_input.pressed(seat, InputAction::GaurdLeft) {
+            Some(Gaurd::Left)
+        } else if action_input.pressed(seat, InputAction::GaurdRight) {
+            Some(Gaurd::Right)
+        } else if action_input.pressed(seat, InputAction::GaurdUp) {
+            Some(Gaurd::Up)
+        } else if action_input.pressed(seat, InputAction::GaurdDown) {
+            Some(Gaurd::Down)
+        } else {
+            None
+        };
     }
 }
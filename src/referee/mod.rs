@@ -0,0 +1,334 @@
+use crate::{
+    bout::{Bout, BoutEvent},
+    combat::next_screen,
+    controls::input::MenuInput,
+    state::{local_bout, GameMode, GameState, Screen},
+};
+use bevy::prelude::*;
+
+/// how long (in seconds) the referee holds the bout after calling halt.
+const HALT_TIME: f32 = 1.0;
+/// what the referee calls to start a bout, each takes an equal share of the countdown. (the
+/// default font has no accents, so no circumflex on "Prets")
+const START_CALLS: [&str; 3] = ["En garde!", "Prets?", "Allez!"];
+/// the countdowns (in seconds) the mode select screen cycles through.
+const COUNTDOWNS: [u8; 4] = [3, 2, 1, 0];
+
+/// how long (in seconds) the referee counts down before each bout, picked on the mode select
+/// screen. 0 starts the bout straight away.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Countdown(pub u8);
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self(COUNTDOWNS[0])
+    }
+}
+
+impl Countdown {
+    /// the next countdown to pick.
+    pub fn next(self) -> Self {
+        let i = COUNTDOWNS.iter().position(|c| *c == self.0);
+
+        Self(COUNTDOWNS[i.map_or(0, |i| (i + 1) % COUNTDOWNS.len())])
+    }
+}
+
+/// the countdown to the bout being fenced next.
+#[derive(Resource, Debug)]
+struct BoutStart(Timer);
+
+/// the referee stopping the bout once it is over. the game moves on once they are done
+/// announcing what happened.
+#[derive(Resource, Debug, Default)]
+pub struct Halt(Option<Call>);
+
+#[derive(Debug)]
+struct Call {
+    /// where the game goes after the halt.
+    next: Screen,
+    /// what the referee says happened.
+    announcement: String,
+    /// wether this is the break between periods, which shows how long is left of it.
+    break_time: bool,
+    timer: Timer,
+}
+
+impl Halt {
+    /// calls halt if `event` ended the bout, the game moves on once the referee is done. a halt
+    /// already called is kept.
+    pub fn call(&mut self, event: BoutEvent, bout: &Bout) {
+        let Some(next) = next_screen(event) else {
+            return;
+        };
+
+        if self.0.is_some() {
+            return;
+        }
+
+        let break_time =
+            event == BoutEvent::TimeUp(Screen::NewBout) && bout.state.priority.is_none();
+        let seconds = if break_time {
+            bout.state.rules.break_time as f32
+        } else {
+            HALT_TIME
+        };
+
+        self.0 = Some(Call {
+            next,
+            announcement: announcement(event, &bout.state),
+            break_time,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        });
+    }
+
+    /// wether halt has been called, and the game has not moved on yet.
+    pub fn called(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[derive(Component)]
+struct HaltText;
+
+/// the line under "Halt!", with what happened.
+#[derive(Component)]
+struct CallText;
+
+#[derive(Component)]
+struct CountdownText;
+
+/// the call being made in the countdown.
+#[derive(Component)]
+struct StartCall;
+
+/// counts down to each bout, calls halt when it ends, and announces the touch (or that there
+/// was none)
+pub struct RefereePlugin;
+
+impl Plugin for RefereePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Halt>()
+            .init_resource::<Countdown>()
+            .add_systems(OnEnter(Screen::NewBout), start_countdown)
+            .add_systems(Update, count_down.run_if(in_state(Screen::NewBout)))
+            .add_systems(OnExit(Screen::NewBout), despawn_countdown_text)
+            .add_systems(
+                Update,
+                (call_halt, skip_break.run_if(local_bout), referee)
+                    .chain()
+                    .run_if(in_state(Screen::Game)),
+            )
+            // the halt is kept through the pause menu, but not shown over it.
+            .add_systems(OnExit(Screen::Game), despawn_halt_text)
+            .add_systems(OnEnter(Screen::NewBout), clear_halt)
+            .add_systems(OnEnter(Screen::Welcome), clear_halt);
+    }
+}
+
+/// starts the countdown to the bout. a spectator joins the hosts bout as it is, with no
+/// countdown.
+fn start_countdown(mut commands: Commands, countdown: Res<Countdown>, game_mode: Res<GameMode>) {
+    let seconds = match *game_mode {
+        GameMode::Spectator => 0,
+        _ => countdown.0,
+    };
+
+    if seconds > 0 {
+        spawn_countdown_text(&mut commands);
+    }
+
+    commands.insert_resource(BoutStart(Timer::from_seconds(
+        seconds as f32,
+        TimerMode::Once,
+    )));
+}
+
+/// makes the calls as the countdown runs, then starts the bout. the fighters stand en garde
+/// untill then, as nothing moves them outside of the bout. a skip press skips the rest of it.
+fn count_down(
+    time: Res<Time>,
+    menu_input: Res<MenuInput>,
+    mut start: ResMut<BoutStart>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut call_query: Query<&mut Text, With<StartCall>>,
+) {
+    start.0.tick(time.delta());
+
+    if start.0.finished() || menu_input.skip {
+        next_state.set(Screen::Game);
+        return;
+    }
+
+    let call = (start.0.fraction() * START_CALLS.len() as f32) as usize;
+
+    if let Ok(mut text) = call_query.get_single_mut() {
+        text.sections[0].value = START_CALLS[call.min(START_CALLS.len() - 1)].to_string();
+    }
+}
+
+/// calls halt on whatever ended the bout on this machine.
+fn call_halt(mut halt: ResMut<Halt>, bout: Res<Bout>, mut bout_events: EventReader<BoutEvent>) {
+    for event in bout_events.read() {
+        halt.call(*event, &bout);
+    }
+}
+
+/// a skip press ends the break between periods early, like it skips the countdown. over the LAN
+/// the break runs out on both machines together.
+fn skip_break(menu_input: Res<MenuInput>, mut halt: ResMut<Halt>) {
+    if !menu_input.skip {
+        return;
+    }
+
+    if let Some(call) = halt.0.as_mut()
+        && call.break_time
+    {
+        let left = call.timer.remaining();
+        call.timer.tick(left);
+    }
+}
+
+/// shows the call while the halt lasts, then moves the game on.
+fn referee(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut halt: ResMut<Halt>,
+    mut next_state: ResMut<NextState<Screen>>,
+    text_query: Query<Entity, With<HaltText>>,
+    mut call_query: Query<&mut Text, With<CallText>>,
+) {
+    let Some(call) = halt.0.as_mut() else {
+        return;
+    };
+
+    if text_query.is_empty() {
+        spawn_halt_text(&mut commands, &call.announcement);
+    }
+
+    call.timer.tick(time.delta());
+
+    if call.break_time
+        && let Ok(mut text) = call_query.get_single_mut()
+    {
+        text.sections[0].value = format!(
+            "{}, {:.0} seconds of break left",
+            call.announcement,
+            call.timer.remaining_secs().ceil()
+        );
+    }
+
+    if call.timer.finished() {
+        next_state.set(call.next);
+        halt.0 = None;
+        text_query
+            .iter()
+            .for_each(|text| commands.entity(text).despawn_recursive());
+    }
+}
+
+/// what the referee calls after halt, `state` is the bout as `event` left it.
+fn announcement(event: BoutEvent, state: &GameState) -> String {
+    match event {
+        BoutEvent::Touch(player, Screen::Victory) => {
+            format!("touch, Player {player:?} wins the match")
+        }
+        BoutEvent::Touch(player, _) => format!("touch, Player {player:?}"),
+        BoutEvent::SideFlip => "the fencers passed each other".to_string(),
+        BoutEvent::Simultaneous => "simultaneous, no touch".to_string(),
+        BoutEvent::TimeUp(Screen::Victory) => match state.winner {
+            Some(winner) => format!("time, Player {winner:?} wins the match"),
+            None => "time".to_string(),
+        },
+        BoutEvent::TimeUp(_) => match state.priority {
+            Some(holder) => format!("time, priority minute with priority to Player {holder:?}"),
+            None => format!("time, end of period {}", state.period - 1),
+        },
+        BoutEvent::Parry(_) | BoutEvent::Beat(_) | BoutEvent::OutOfBounds(_) => String::new(),
+    }
+}
+
+fn spawn_halt_text(commands: &mut Commands, call: &str) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Start,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::top(Val::Vh(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            HaltText,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Halt!",
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    call,
+                    TextStyle {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                ),
+                CallText,
+            ));
+        });
+}
+
+fn spawn_countdown_text(commands: &mut Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Start,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::top(Val::Vh(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            CountdownText,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    START_CALLS[0],
+                    TextStyle {
+                        font_size: 32.0,
+                        ..default()
+                    },
+                ),
+                StartCall,
+            ));
+        });
+}
+
+fn despawn_countdown_text(mut commands: Commands, text_query: Query<Entity, With<CountdownText>>) {
+    text_query
+        .iter()
+        .for_each(|text| commands.entity(text).despawn_recursive());
+}
+
+fn despawn_halt_text(mut commands: Commands, text_query: Query<Entity, With<HaltText>>) {
+    text_query
+        .iter()
+        .for_each(|text| commands.entity(text).despawn_recursive());
+}
+
+fn clear_halt(mut halt: ResMut<Halt>) {
+    halt.0 = None;
+}
@@ -0,0 +1,334 @@
+use crate::{
+    bout::{Bout, BoutEvent, TickInputs, TICK},
+    combat::next_screen,
+    controls::input::MenuInput,
+    despawn_buttons, resume_clock,
+    state::{GameMode, Screen},
+    ButtonMarker,
+};
+use bevy::prelude::*;
+use recording::Recording;
+use std::path::PathBuf;
+
+pub mod recording;
+
+/// the playback speeds the viewer cycles through, set on the game clock.
+const SPEEDS: [f32; 4] = [1.0, 0.5, 0.25, 0.125];
+
+/// the bout being recorded, if there is one.
+#[derive(Resource, Default)]
+pub struct Recorder(Option<Recording>);
+
+/// the replay being watched.
+#[derive(Resource)]
+struct Viewer {
+    /// every saved replay, newest first.
+    files: Vec<PathBuf>,
+    /// which of the files is being watched.
+    index: usize,
+    /// None if there are no replays, or the one picked could not be read.
+    recording: Option<Recording>,
+    playing: bool,
+    /// which of the `SPEEDS` to play at.
+    speed: usize,
+    /// seconds of playback not yet simulated.
+    behind: f32,
+    /// the last thing that happened, and on which tick.
+    last_event: Option<(u64, BoutEvent)>,
+}
+
+impl Viewer {
+    /// starts watching the replay at `index` from the beginning.
+    fn open(&mut self, index: usize, bout: &mut Bout) {
+        self.index = index;
+        self.playing = false;
+        self.behind = 0.0;
+        self.last_event = None;
+        self.recording = self
+            .files
+            .get(index)
+            .and_then(|path| match Recording::load(path) {
+                Ok(recording) => Some(recording),
+                Err(e) => {
+                    error!("could not read the replay at {}: {e}", path.display());
+                    None
+                }
+            });
+
+        if let Some(recording) = &self.recording {
+            *bout = recording.initial.clone();
+        }
+    }
+
+    fn seek(&mut self, bout: &mut Bout, tick: u64) {
+        let Some(recording) = &self.recording else {
+            return;
+        };
+
+        if tick < bout.tick {
+            self.last_event = None;
+        }
+
+        if let Some(last) = recording.seek(bout, tick).last() {
+            self.last_event = Some(*last);
+        }
+    }
+
+    fn len(&self) -> u64 {
+        self.recording.as_ref().map_or(0, Recording::len)
+    }
+}
+
+#[derive(Component)]
+struct ReplayText;
+
+/// records every bout fenced on this machine, and lets the player watch them again
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recorder>()
+            .add_systems(OnEnter(Screen::Game), start_recording)
+            .add_systems(Update, record)
+            // a bout left before it ended is not worth keeping.
+            .add_systems(OnEnter(Screen::NewBout), stop_recording)
+            .add_systems(OnEnter(Screen::Welcome), stop_recording)
+            .add_systems(OnEnter(Screen::Replay), open_viewer)
+            .add_systems(OnEnter(Screen::Replay), spawn_overlay)
+            .add_systems(OnExit(Screen::Replay), despawn_buttons)
+            .add_systems(OnExit(Screen::Replay), (close_viewer, resume_clock))
+            .add_systems(
+                Update,
+                (replay_controls, play, show_overlay)
+                    .chain()
+                    .run_if(in_state(Screen::Replay)),
+            );
+    }
+}
+
+/// starts recording when a bout starts. coming back from the pause menu keeps the recording
+/// going.
+fn start_recording(mut recorder: ResMut<Recorder>, bout: Res<Bout>, game_mode: Res<GameMode>) {
+    if recorder.0.is_none() && *game_mode != GameMode::Spectator {
+        recorder.0 = Some(Recording::new(bout.clone()));
+    }
+}
+
+/// adds each ticks inputs to the recording, and saves it once the bout ends.
+fn record(
+    mut recorder: ResMut<Recorder>,
+    mut tick_inputs: EventReader<TickInputs>,
+    mut bout_events: EventReader<BoutEvent>,
+) {
+    let inputs: Vec<TickInputs> = tick_inputs.read().copied().collect();
+    let ended = bout_events
+        .read()
+        .any(|event| next_screen(*event).is_some());
+
+    let Some(recording) = recorder.0.as_mut() else {
+        return;
+    };
+
+    recording.inputs.extend(inputs);
+
+    if !ended {
+        return;
+    }
+
+    if let Some(mut recording) = recorder.0.take() {
+        recording.trim();
+
+        match recording.save() {
+            Ok(path) => info!("saved a replay of the bout to {}", path.display()),
+            Err(e) => error!("failed to save a replay of the bout: {e}"),
+        }
+    }
+}
+
+fn stop_recording(mut recorder: ResMut<Recorder>) {
+    recorder.0 = None;
+}
+
+fn open_viewer(mut commands: Commands, mut bout: ResMut<Bout>) {
+    let mut viewer = Viewer {
+        files: recording::list(),
+        index: 0,
+        recording: None,
+        playing: false,
+        speed: 0,
+        behind: 0.0,
+        last_event: None,
+    };
+    viewer.open(0, &mut bout);
+
+    commands.insert_resource(viewer);
+}
+
+fn close_viewer(mut commands: Commands) {
+    commands.remove_resource::<Viewer>();
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            ButtonMarker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 12.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                ReplayText,
+            ));
+        });
+}
+
+/// space plays/pauses, comma and period step a tick, S changes the speed, the arrow keys scrub
+/// a second, home/end jump to the start/end, page up/down switch replays, and escape leaves.
+fn replay_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    menu_input: Res<MenuInput>,
+    mut viewer: ResMut<Viewer>,
+    mut bout: ResMut<Bout>,
+    mut next_state: ResMut<NextState<Screen>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let second = (1.0 / TICK) as u64;
+    let tick = bout.tick;
+
+    if menu_input.back {
+        next_state.set(Screen::Welcome);
+    } else if keyboard_input.just_pressed(KeyCode::Space) {
+        if !viewer.playing && (bout.over || tick >= viewer.len()) {
+            viewer.seek(&mut bout, 0);
+        }
+
+        viewer.playing = !viewer.playing;
+    } else if keyboard_input.just_pressed(KeyCode::Period) {
+        viewer.playing = false;
+        viewer.seek(&mut bout, tick + 1);
+    } else if keyboard_input.just_pressed(KeyCode::Comma) {
+        viewer.playing = false;
+        viewer.seek(&mut bout, tick.saturating_sub(1));
+    } else if keyboard_input.just_pressed(KeyCode::KeyS) {
+        viewer.speed = (viewer.speed + 1) % SPEEDS.len();
+        time.set_relative_speed(SPEEDS[viewer.speed]);
+    } else if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        viewer.seek(&mut bout, tick + second);
+    } else if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        viewer.seek(&mut bout, tick.saturating_sub(second));
+    } else if keyboard_input.just_pressed(KeyCode::Home) {
+        viewer.seek(&mut bout, 0);
+    } else if keyboard_input.just_pressed(KeyCode::End) {
+        let end = viewer.len();
+        viewer.seek(&mut bout, end);
+    } else if keyboard_input.just_pressed(KeyCode::PageUp) && viewer.index > 0 {
+        let newer = viewer.index - 1;
+        viewer.open(newer, &mut bout);
+    } else if keyboard_input.just_pressed(KeyCode::PageDown)
+        && viewer.index + 1 < viewer.files.len()
+    {
+        let older = viewer.index + 1;
+        viewer.open(older, &mut bout);
+    }
+}
+
+/// moves the replay along with the game clock, which runs at the chosen speed.
+fn play(time: Res<Time>, mut viewer: ResMut<Viewer>, mut bout: ResMut<Bout>) {
+    if !viewer.playing {
+        return;
+    }
+
+    viewer.behind += time.delta_seconds();
+
+    let ticks = (viewer.behind / TICK) as u64;
+    viewer.behind -= ticks as f32 * TICK;
+
+    let to = bout.tick + ticks;
+    viewer.seek(&mut bout, to);
+
+    if bout.over || bout.tick >= viewer.len() {
+        viewer.playing = false;
+    }
+}
+
+fn show_overlay(
+    viewer: Res<Viewer>,
+    bout: Res<Bout>,
+    mut text_query: Query<&mut Text, With<ReplayText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let Some(path) = viewer.files.get(viewer.index) else {
+        text.sections[0].value =
+            "no replays yet, every bout you fence is recorded\n\nesc: back".to_string();
+        return;
+    };
+
+    let file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if viewer.recording.is_none() {
+        text.sections[0].value = format!(
+            "replay {}/{}: {file}\ncould not read this replay\n\npage up/down: other replays, esc: back",
+            viewer.index + 1,
+            viewer.files.len()
+        );
+        return;
+    }
+
+    let last_event = match viewer.last_event {
+        Some((tick, event)) => format!("tick {tick}: {}", describe(event)),
+        None => "-".to_string(),
+    };
+
+    text.sections[0].value = format!(
+        "replay {}/{}: {file}\n\
+         tick {}/{} ({:.2}s) {}x {}\n\
+         score {} - {}\n\
+         last: {last_event}\n\n\
+         space: play/pause, ,/.: step, s: speed, left/right: scrub, home/end: start/end\n\
+         page up/down: other replays, esc: back",
+        viewer.index + 1,
+        viewer.files.len(),
+        bout.tick,
+        viewer.len(),
+        bout.tick as f32 * TICK,
+        SPEEDS[viewer.speed],
+        if viewer.playing { "playing" } else { "paused" },
+        bout.state.p1_score.touches,
+        bout.state.p2_score.touches,
+    );
+}
+
+fn describe(event: BoutEvent) -> String {
+    match event {
+        BoutEvent::Touch(player, _) => format!("player {player:?} scored a touch"),
+        BoutEvent::Parry(player) => format!("player {player:?} parried"),
+        BoutEvent::Beat(player) => format!("player {player:?} beat the blade"),
+        BoutEvent::SideFlip => "the fighters moved passed each other".to_string(),
+        BoutEvent::Simultaneous => "simultaneous, no touch".to_string(),
+        BoutEvent::TimeUp(_) => "the clock ran out".to_string(),
+        BoutEvent::OutOfBounds(player) => {
+            format!("player {player:?} was stopped at the end of the piste")
+        }
+    }
+}
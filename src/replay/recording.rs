@@ -0,0 +1,343 @@
+use crate::{
+    bout::{Bout, BoutEvent, TickInputs},
+    lan::protocol::{Reader, Writer},
+    paths::data_dir,
+};
+use bevy::prelude::*;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// every replay file starts with these bytes.
+const MAGIC: &[u8; 4] = b"FGRP";
+/// bump whenever the layout of a replay changes. replays are written with the same encoding as
+/// the LAN protocol, so a change to how fighters or inputs are sent counts too.
+const FILE_VERSION: u8 = 6;
+const EXTENSION: &str = "replay";
+/// how many replays are kept, the oldest are deleted to make room for new ones.
+const MAX_REPLAYS: usize = 100;
+/// replays longer than this (in ticks, an hour) are assumed to be corrupt.
+const MAX_TICKS: usize = 64 * 60 * 60;
+
+/// the directory replays are saved to.
+pub fn replay_dir() -> PathBuf {
+    data_dir().join("replays")
+}
+
+/// every saved replay, newest first.
+pub fn list() -> Vec<PathBuf> {
+    list_in(&replay_dir())
+}
+
+/// every replay in `dir`, newest first.
+fn list_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut replays: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .collect();
+
+    // the file names are the time the bout ended.
+    replays.sort();
+    replays.reverse();
+
+    replays
+}
+
+/// a bout as it was when it started, and what both fighters did on every tick after. the rules
+/// are deterministic (even the priority coin is tossed from the bout itself), so that is enough
+/// to fence it again exactly.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub initial: Bout,
+    /// both fighters inputs, one pair per tick.
+    pub inputs: Vec<TickInputs>,
+}
+
+impl Recording {
+    pub fn new(initial: Bout) -> Self {
+        Self {
+            initial,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// how many ticks were recorded.
+    pub fn len(&self) -> u64 {
+        self.inputs.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// drops the ticks recorded after the bout ended.
+    pub fn trim(&mut self) {
+        let mut bout = self.initial.clone();
+        let before_end = self
+            .inputs
+            .iter()
+            .take_while(|inputs| {
+                bout.step(inputs.p1, inputs.p2);
+                !bout.over
+            })
+            .count();
+
+        self.inputs.truncate(before_end + 1);
+    }
+
+    /// puts `bout` where it was after `tick` ticks, fencing it again from the start if that
+    /// is behind it. returns what happened on the ticks fenced, and when.
+    pub fn seek(&self, bout: &mut Bout, tick: u64) -> Vec<(u64, BoutEvent)> {
+        let tick = tick.min(self.len());
+        let mut events = Vec::new();
+
+        if tick < bout.tick {
+            *bout = self.initial.clone();
+        }
+
+        for inputs in self
+            .inputs
+            .iter()
+            .take(tick as usize)
+            .skip(bout.tick as usize)
+        {
+            for event in bout.step(inputs.p1, inputs.p2) {
+                events.push((bout.tick, event));
+            }
+        }
+
+        events
+    }
+
+    /// writes the recording into a new file in the replay directory, and deletes the oldest
+    /// replays if there are too many. returns where it was saved.
+    pub fn save(&self) -> io::Result<PathBuf> {
+        self.save_in(&replay_dir())
+    }
+
+    fn save_in(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let ended = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let path = dir.join(format!("{ended:015}.{EXTENSION}"));
+        fs::write(&path, self.encode())?;
+
+        for old in list_in(dir).iter().skip(MAX_REPLAYS) {
+            if let Err(e) = fs::remove_file(old) {
+                warn!("could not delete the old replay {}: {e}", old.display());
+            }
+        }
+
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "not a replay file"))
+    }
+
+    /// the starting state is written out in full, the inputs as runs of ticks that repeat the
+    /// same pair (fencers hold keys down much longer than a tick).
+    fn encode(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::with_capacity(256));
+        w.0.extend_from_slice(MAGIC);
+        w.u8(FILE_VERSION);
+        w.fighter(&self.initial.p1);
+        w.fighter(&self.initial.p2);
+        w.state(&self.initial.state);
+
+        let runs: Vec<&[TickInputs]> = self
+            .inputs
+            .chunk_by(|a, b| a == b)
+            .flat_map(|run| run.chunks(u16::MAX as usize))
+            .collect();
+
+        w.u32(runs.len() as u32);
+
+        for run in runs {
+            w.u16(run.len() as u16);
+            w.input(&run[0].p1);
+            w.input(&run[0].p2);
+        }
+
+        w.0
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader(bytes);
+
+        if r.take(4)? != MAGIC || r.u8()? != FILE_VERSION {
+            return None;
+        }
+
+        let initial = Bout {
+            p1: r.fighter()?,
+            p2: r.fighter()?,
+            state: r.state()?,
+            tick: 0,
+            over: false,
+        };
+        let mut inputs = Vec::new();
+
+        for _ in 0..r.u32()? {
+            let len = r.u16()? as usize;
+            let tick = TickInputs {
+                p1: r.input()?,
+                p2: r.input()?,
+            };
+
+            if inputs.len() + len > MAX_TICKS {
+                return None;
+            }
+
+            inputs.extend(std::iter::repeat_n(tick, len));
+        }
+
+        r.0.is_empty().then_some(Self { initial, inputs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::offensive,
+        bout::FighterInput,
+        fighter::{Controller, Move, Player},
+        rng::Rng,
+    };
+
+    /// how far into the bout to seek back to.
+    const MIDWAY: u64 = 100;
+
+    /// a directory in the temp directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("fencing-game-{}-{name}", std::process::id())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// fences a bout between the scripted computer player and someone pressing random keys,
+    /// recording it as the game does (and for a while after it ends). returns the recording,
+    /// the bout midway, and the bout at the end.
+    fn fence() -> (Recording, Bout, Bout) {
+        const MOVES: [Move; 4] = [Move::Advance, Move::Retreat, Move::Lunge, Move::Beat];
+
+        let mut bout = Bout::new();
+        // who controls a fighter is not kept in a replay, it always comes back as remote.
+        bout.p1.controller = Controller::Remote;
+        bout.p2.controller = Controller::Remote;
+
+        let mut recording = Recording::new(bout.clone());
+        let mut rng = Rng::new(3);
+        let mut midway = None;
+        let mut extra = 0;
+
+        while extra < 10 {
+            let inputs = TickInputs {
+                p1: offensive(&bout, Player::One),
+                p2: FighterInput {
+                    action: Some(MOVES[rng.below(MOVES.len())]),
+                    ..Default::default()
+                },
+            };
+            recording.inputs.push(inputs);
+
+            if bout.over {
+                extra += 1;
+            } else {
+                bout.step(inputs.p1, inputs.p2);
+            }
+
+            if bout.tick == MIDWAY && midway.is_none() {
+                midway = Some(bout.clone());
+            }
+        }
+
+        (recording, midway.expect("the bout ended too soon"), bout)
+    }
+
+    #[test]
+    fn saved_replay_plays_back_the_same() {
+        let (mut recording, midway, end) = fence();
+        recording.trim();
+        assert_eq!(recording.len(), end.tick);
+
+        let dir = TempDir::new("replays");
+        let path = recording.save_in(&dir.0).unwrap();
+        assert_eq!(list_in(&dir.0), vec![path.clone()]);
+
+        let replay = Recording::load(&path).unwrap();
+        let mut bout = replay.initial.clone();
+        replay.seek(&mut bout, replay.len());
+
+        assert!(bout.over);
+        assert_eq!(format!("{bout:?}"), format!("{end:?}"));
+
+        // seeking backwards fences it again from the start.
+        replay.seek(&mut bout, MIDWAY);
+        assert_eq!(format!("{bout:?}"), format!("{midway:?}"));
+    }
+
+    #[test]
+    fn corrupt_replays_are_rejected() {
+        let (recording, ..) = fence();
+        let bytes = recording.encode();
+        assert!(Recording::decode(&bytes).is_some());
+
+        assert!(Recording::decode(&bytes[..bytes.len() - 1]).is_none());
+        assert!(Recording::decode(&[bytes.as_slice(), &[0]].concat()).is_none());
+        assert!(Recording::decode(&[b"FGQT", &bytes[4..]].concat()).is_none());
+
+        let mut version = bytes.clone();
+        version[4] = FILE_VERSION + 1;
+        assert!(Recording::decode(&version).is_none());
+
+        let dir = TempDir::new("corrupt");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join(format!("junk.{EXTENSION}"));
+        fs::write(&path, b"not a replay").unwrap();
+        assert_eq!(
+            Recording::load(&path).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn overlong_replays_are_rejected() {
+        let mut recording = Recording::new(Bout::new());
+        recording.inputs = vec![
+            TickInputs {
+                p1: FighterInput::default(),
+                p2: FighterInput::default(),
+            };
+            MAX_TICKS + 1
+        ];
+
+        assert!(Recording::decode(&recording.encode()).is_none());
+
+        recording.inputs.pop();
+        assert_eq!(
+            Recording::decode(&recording.encode()).map(|replay| replay.len()),
+            Some(MAX_TICKS as u64)
+        );
+    }
+}
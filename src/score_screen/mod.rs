@@ -1,6 +1,5 @@
 use crate::{
-    despawn_buttons,
-    state::{GameState, Screen},
+    bout::Bout, controls::input::MenuInput, despawn_buttons, state::Screen, ButtonInteractions,
     ButtonMarker,
 };
 use bevy::prelude::*;
@@ -50,7 +49,7 @@ impl Plugin for ScoreScreenPlugin {
 }
 
 /// spawns the buttons (and button text) of the score menu
-fn spawn_buttons(mut commands: Commands, world_state: Res<GameState>) {
+fn spawn_buttons(mut commands: Commands, bout: Res<Bout>) {
     debug!("spawning score touch menu buttons");
 
     commands
@@ -72,7 +71,11 @@ fn spawn_buttons(mut commands: Commands, world_state: Res<GameState>) {
         .with_children(|parent| {
             parent.spawn(TextBundle::from_section(
                 // Accepts a `String` or any type that converts into a `String`, such as `&str`
-                format!("Player {:?}, scored!", world_state.row.unwrap()),
+                format!(
+                    "Player {:?} scored with {}!",
+                    bout.state.row.unwrap(),
+                    bout.state.phrase().label()
+                ),
                 TextStyle {
                     // This font is loaded and will be used instead of the default font.
                     // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
@@ -87,7 +90,7 @@ fn spawn_buttons(mut commands: Commands, world_state: Res<GameState>) {
                 // Accepts a `String` or any type that converts into a `String`, such as `&str`
                 format!(
                     "Player One => {:<2} | Player Two => {:<2}",
-                    world_state.p1_score.touches, world_state.p2_score.touches
+                    bout.state.p1_score.touches, bout.state.p2_score.touches
                 ),
                 TextStyle {
                     // This font is loaded and will be used instead of the default font.
@@ -178,28 +181,19 @@ fn spawn_buttons(mut commands: Commands, world_state: Res<GameState>) {
 }
 
 /// handles using the keyboard to select a button
-fn keyboard_select(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut button_selection: ResMut<SelectedButton>,
-) {
+fn keyboard_select(menu_input: Res<MenuInput>, mut button_selection: ResMut<SelectedButton>) {
     match button_selection.0 {
         Some(button) => {
-            if keyboard_input.just_released(KeyCode::ArrowUp)
-                || keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.prev || menu_input.next {
                 debug!("changing button selection with keeb");
                 button_selection.0 = Some(!button);
-            } else if keyboard_input.just_released(KeyCode::Enter) {
+            } else if menu_input.press {
                 debug!("pressing button with keeb");
                 button_selection.1 = Interaction::Pressed;
             }
         }
         None => {
-            if keyboard_input.just_released(KeyCode::ArrowUp)
-                || keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.prev || menu_input.next {
                 debug!("selecting continue button with keeb");
                 button_selection.0 = Some(ScoreMenuButton::Continue);
                 button_selection.1 = Interaction::Hovered;
@@ -210,7 +204,7 @@ fn keyboard_select(
 
 /// handles using the mouse to select a button
 fn mouse_select(
-    mut interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: ButtonInteractions,
     mut text_query: Query<&ScoreMenuButton, With<Text>>,
     mut button_selection: ResMut<SelectedButton>,
 ) {
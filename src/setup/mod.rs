@@ -1,7 +1,8 @@
 use crate::{
+    ai::Player2Marker,
     combat::{GaurdIconMarker, ScoreBoard},
+    player::PlayerMarker,
     state::Screen,
-    Fighter,
 };
 use bevy::{core::FrameCount, prelude::*, window::PrimaryWindow};
 
@@ -11,8 +12,11 @@ impl Plugin for SetupPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_camera)
             .add_systems(OnEnter(Screen::Game), setup_camera)
+            .add_systems(OnEnter(Screen::Replay), setup_camera)
+            .add_systems(OnExit(Screen::Replay), cleanup_after_bout)
+            .add_systems(OnEnter(Screen::HowToPlay), setup_camera)
+            .add_systems(OnExit(Screen::HowToPlay), cleanup_after_bout)
             .add_systems(OnEnter(Screen::NewBout), cleanup_after_bout)
-            .add_systems(OnEnter(Screen::NewBout), start_game)
             .add_systems(Update, make_visible.run_if(in_state(Screen::Setup)));
     }
 }
@@ -54,11 +58,17 @@ fn make_visible(
 
 pub fn cleanup_after_bout(
     mut commands: Commands,
-    // The "With<Fighter/GaurdIconMarker/ScoreBoard>" is nessesary because of the generic nature
+    // The "With<PlayerMarker/Player2Marker/GaurdIconMarker/ScoreBoard>" is nessesary because of the generic nature
     // of the "Query<Entity>" part. Without the restiction the camera & EVERYTHING ELSE gets
     // despawned along with the fighters, gaurd icons, and score board. which woudld breaks the
     // game.
-    fighters: Query<Entity, With<Fighter>>,
+    fighters: Query<
+        Entity,
+        (
+            Or<(With<PlayerMarker>, With<Player2Marker>)>,
+            Without<GaurdIconMarker>,
+        ),
+    >,
     gaurd_icons: Query<Entity, With<GaurdIconMarker>>,
     score_boards: Query<Entity, With<ScoreBoard>>,
 ) {
@@ -72,7 +82,3 @@ pub fn cleanup_after_bout(
         .iter()
         .for_each(|board| commands.entity(board).despawn());
 }
-
-fn start_game(mut next_state: ResMut<NextState<Screen>>) {
-    next_state.set(Screen::Game)
-}
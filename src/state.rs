@@ -1,5 +1,20 @@
-use crate::Player;
+use crate::{
+    bout::TICK,
+    fighter::{Controller, Handed, Player},
+};
 use bevy::prelude::*;
+use std::cmp::Ordering;
+
+/// how many touches a match can be fenced to, the mode select screen cycles through them.
+const TOUCHES: [u8; 3] = [5, 10, 15];
+/// the clocks a match can be fenced with, how long (in seconds) each period lasts and how many
+/// there are. the mode select screen cycles through them.
+const CLOCKS: [(Option<u16>, u8); 3] = [(None, 1), (Some(180), 1), (Some(180), 3)];
+/// how long (in seconds) the break between periods can last, the mode select screen cycles
+/// through them.
+const BREAKS: [u16; 3] = [60, 30, 90];
+/// how long (in seconds) the priority minute lasts.
+const PRIORITY_MINUTE: u16 = 60;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
 pub enum Screen {
@@ -13,9 +28,13 @@ pub enum Screen {
     /// choose to duel the computer, duel a human (over a LAN connection), or spectate a human vs.
     /// human match
     ModeSelect,
+    /// lists the LAN bouts waiting for an opponent.
+    Lobby,
+    /// host a bout over the LAN, or join one by address.
+    Lan,
     /// represents that the bout is being ongoing
     Game,
-    /// the pause menu (only accessable when dueling the computer)
+    /// the pause menu (only accessable when the bout is not over the LAN)
     PauseMenu,
     /// used to clean up from last bout and setup for next bout.
     NewBout,
@@ -29,16 +48,203 @@ pub enum Screen {
     TouchScored,
     /// announces which player scored the touch
     MatchWon,
+    /// plays back recorded bouts.
+    Replay,
     /// used to exit the game
     ExitGame,
 }
 
-#[derive(Resource, Debug)]
+/// who is fencing who.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    /// player one against the computer.
+    VsComputer,
+    /// two people fencing on this machine.
+    VsHuman,
+    /// hosting a LAN bout. this machine fences as player one.
+    LanHost,
+    /// joined someone elses LAN bout. this machine fences as player two.
+    LanClient,
+    /// watching someone elses LAN bout.
+    Spectator,
+}
+
+impl GameMode {
+    /// wether the bout is simulated on this machine alone, rather than over the LAN.
+    pub fn local(self) -> bool {
+        matches!(self, Self::VsComputer | Self::VsHuman)
+    }
+
+    /// who controls player one and player two.
+    pub fn controllers(self) -> (Controller, Controller) {
+        match self {
+            Self::VsComputer => (Controller::Player, Controller::Computer),
+            Self::VsHuman => (Controller::Player, Controller::Player),
+            Self::LanHost => (Controller::Player, Controller::Remote),
+            Self::LanClient => (Controller::Remote, Controller::Player),
+            Self::Spectator => (Controller::Remote, Controller::Remote),
+        }
+    }
+}
+
+/// which hand each fighter holds their sword in, picked on the mode select screen. over the
+/// LAN, the hosts pick is used.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Handedness {
+    pub p1: Handed,
+    pub p2: Handed,
+}
+
+/// how a match is fenced, picked on the mode select screen. over the LAN, the hosts pick is used.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchRules {
+    /// how many touches it takes to win a match.
+    pub touches_to_win: u8,
+    /// how long (in seconds) each period lasts, None fences without a clock.
+    pub period: Option<u16>,
+    /// how many periods the match is fenced over.
+    pub periods: u8,
+    /// how long (in seconds) the break between periods lasts.
+    pub break_time: u16,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            touches_to_win: 15,
+            period: None,
+            periods: 1,
+            break_time: BREAKS[0],
+        }
+    }
+}
+
+impl MatchRules {
+    /// the next number of touches to fence to.
+    pub fn next_touches(self) -> Self {
+        let i = TOUCHES.iter().position(|t| *t == self.touches_to_win);
+
+        Self {
+            touches_to_win: TOUCHES[i.map_or(0, |i| (i + 1) % TOUCHES.len())],
+            ..self
+        }
+    }
+
+    /// the next clock to fence with.
+    pub fn next_clock(self) -> Self {
+        let i = CLOCKS
+            .iter()
+            .position(|c| *c == (self.period, self.periods));
+        let (period, periods) = CLOCKS[i.map_or(0, |i| (i + 1) % CLOCKS.len())];
+
+        Self {
+            period,
+            periods,
+            ..self
+        }
+    }
+
+    /// the next break between periods to fence with.
+    pub fn next_break(self) -> Self {
+        let i = BREAKS.iter().position(|b| *b == self.break_time);
+
+        Self {
+            break_time: BREAKS[i.map_or(0, |i| (i + 1) % BREAKS.len())],
+            ..self
+        }
+    }
+
+    /// how many ticks a period lasts, 0 without a clock.
+    pub fn period_ticks(&self) -> u32 {
+        self.period.map_or(0, seconds_to_ticks)
+    }
+}
+
+fn seconds_to_ticks(seconds: u16) -> u32 {
+    (seconds as f32 / TICK) as u32
+}
+
+/// run condition for systems that only make sense when the bout is simulated on this machine
+/// alone.
+pub fn local_bout(game_mode: Res<GameMode>) -> bool {
+    game_mode.local()
+}
+
+#[derive(Debug, Clone)]
 pub struct GameState {
     pub row: Option<Player>,
     pub p1_score: Score,
     pub p2_score: Score,
     pub lunger: Option<Player>,
+    /// the parrying fighters chance to hit back, while it lasts.
+    pub riposte: Option<Riposte>,
+    pub rules: MatchRules,
+    /// which period is being fenced, counting from 1.
+    pub period: u8,
+    /// ticks left on the clock. unused without one.
+    pub clock: u32,
+    /// who wins if the priority minute runs out, while it is being fenced.
+    pub priority: Option<Player>,
+    /// who won the last match.
+    pub winner: Option<Player>,
+    /// the touches scored so far this match, in the order they were scored.
+    pub timeline: Vec<ScoredTouch>,
+    /// the touches of the last match won, for the victory screen.
+    pub last_match: Vec<ScoredTouch>,
+}
+
+/// a touch, as it goes in the match timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoredTouch {
+    /// who scored it.
+    pub player: Player,
+    /// what it was scored with.
+    pub phrase: Phrase,
+    /// the period it was scored in.
+    pub period: u8,
+    /// seconds left on the clock when it was scored. unused without one.
+    pub seconds: u16,
+}
+
+/// the chance to hit back that a parry earns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Riposte {
+    /// who parried.
+    pub player: Player,
+    /// ticks left to start the riposte in.
+    pub ticks_left: u16,
+    /// how many parries into the phrase this is, 1 after the first parry, 2 after a
+    /// counter-parry, and so on.
+    pub parries: u8,
+    /// wether the riposte has been thrown.
+    pub thrown: bool,
+}
+
+/// what a touch was scored with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phrase {
+    /// the first attack of the phrase.
+    Attack,
+    /// hitting back after parrying the attack.
+    Riposte,
+    /// hitting back after parrying a riposte.
+    CounterRiposte,
+}
+
+impl Phrase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Attack => "an attack",
+            Self::Riposte => "a riposte",
+            Self::CounterRiposte => "a counter-riposte",
+        }
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameState {
@@ -48,12 +254,68 @@ impl GameState {
             p1_score: Score::default(),
             p2_score: Score::default(),
             lunger: None,
+            riposte: None,
+            rules: MatchRules::default(),
+            period: 1,
+            clock: MatchRules::default().period_ticks(),
+            priority: None,
+            winner: None,
+            timeline: Vec::new(),
+            last_match: Vec::new(),
+        }
+    }
+
+    /// fences the match by `rules`. changing them starts the clock over.
+    pub fn set_rules(&mut self, rules: MatchRules) {
+        if self.rules != rules {
+            self.rules = rules;
+            self.restart_clock();
+        }
+    }
+
+    /// puts the clock back to the start of the first period.
+    fn restart_clock(&mut self) {
+        self.period = 1;
+        self.clock = self.rules.period_ticks();
+        self.priority = None;
+    }
+
+    pub fn score(&self, player: Player) -> &Score {
+        match player {
+            Player::One => &self.p1_score,
+            Player::Two => &self.p2_score,
+        }
+    }
+
+    fn score_mut(&mut self, player: Player) -> &mut Score {
+        match player {
+            Player::One => &mut self.p1_score,
+            Player::Two => &mut self.p2_score,
+        }
+    }
+
+    /// who has scored more touches, if anyone.
+    pub fn leader(&self) -> Option<Player> {
+        match self.p1_score.touches.cmp(&self.p2_score.touches) {
+            Ordering::Greater => Some(Player::One),
+            Ordering::Less => Some(Player::Two),
+            Ordering::Equal => None,
         }
     }
 
     pub fn reset(&mut self) {
         self.row = None;
         self.lunger = None;
+        self.riposte = None;
+    }
+
+    /// how far into the phrase the bout is, a touch scored now is scored with this.
+    pub fn phrase(&self) -> Phrase {
+        match self.riposte {
+            None => Phrase::Attack,
+            Some(Riposte { parries: 1, .. }) => Phrase::Riposte,
+            Some(_) => Phrase::CounterRiposte,
+        }
     }
 
     pub fn lunge(&mut self, player: Player) {
@@ -65,38 +327,66 @@ impl GameState {
     pub fn reset_scores(&mut self) {
         self.p1_score = Score::default();
         self.p2_score = Score::default();
+        self.winner = None;
+        self.timeline.clear();
+        self.last_match.clear();
+        self.restart_clock();
     }
 
     pub fn score_touch(&mut self, player: Player) -> Screen {
-        let mut next_state: Screen = Screen::TouchScored;
+        self.score_mut(player).score_touch();
+        self.timeline.push(ScoredTouch {
+            player,
+            phrase: self.phrase(),
+            period: self.period,
+            seconds: (self.clock as f32 * TICK).ceil() as u16,
+        });
 
-        match player {
-            Player::One => {
-                if self.p1_score.touches == 14 {
-                    info!("match over!");
-                    self.p2_score.touches = 0;
-                    next_state = Screen::Victory;
-                }
-
-                self.p1_score.score_touch();
-            }
-            Player::Two => {
-                if self.p2_score.touches == 14 {
-                    info!("match over!");
-                    self.p1_score.touches = 0;
-                    next_state = Screen::Victory;
-                }
-
-                self.p2_score.score_touch();
-            }
+        // the first touch of the priority minute wins.
+        if self.priority.is_some() || self.score(player).touches >= self.rules.touches_to_win {
+            info!("match over!");
+            return self.win_match(player);
         }
 
-        // TODO: return "next_state" once Victory and TouchScored screens are implemented.
+        Screen::TouchScored
+    }
+
+    /// the period ran out. moves on to the next one, or the priority minute if the score is
+    /// tied at the end of the last one, with `coin` getting priority. returns where the game
+    /// should go next.
+    pub fn time_up(&mut self, coin: Player) -> Screen {
+        if let Some(holder) = self.priority {
+            info!("the priority minute ran out");
+            return self.win_match(holder);
+        }
 
-        next_state
+        if self.period < self.rules.periods {
+            self.period += 1;
+            self.clock = self.rules.period_ticks();
+            return Screen::NewBout;
+        }
 
-        // // TODO: remove below once Victory and TouchScored screens are implemented.
-        // Screen::NewBout
+        match self.leader() {
+            Some(leader) => self.win_match(leader),
+            None => {
+                info!("tied on time, priority to player {:?}", coin);
+                self.priority = Some(coin);
+                self.clock = seconds_to_ticks(PRIORITY_MINUTE);
+                Screen::NewBout
+            }
+        }
+    }
+
+    /// ends the match with `player` winning it, and sets up for the next one.
+    fn win_match(&mut self, player: Player) -> Screen {
+        self.score_mut(player).matches += 1;
+        self.p1_score.touches = 0;
+        self.p2_score.touches = 0;
+        self.winner = Some(player);
+        self.last_match = std::mem::take(&mut self.timeline);
+        self.restart_clock();
+
+        Screen::Victory
     }
 }
 
@@ -111,10 +401,99 @@ pub struct Score {
 impl Score {
     pub fn score_touch(&mut self) {
         self.touches += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if self.touches == 15 {
-            self.matches += 1;
-            self.touches = 0;
+    /// a match fenced to `touches_to_win`, over `periods` 3 minute periods.
+    fn fenced_to(touches_to_win: u8, periods: u8) -> GameState {
+        let mut state = GameState::new();
+        state.set_rules(MatchRules {
+            touches_to_win,
+            period: Some(180),
+            periods,
+            ..default()
+        });
+        state
+    }
+
+    #[test]
+    fn match_ends_at_touches_to_win() {
+        let mut state = fenced_to(5, 1);
+
+        for _ in 0..4 {
+            assert_eq!(state.score_touch(Player::One), Screen::TouchScored);
         }
+
+        assert_eq!(state.score_touch(Player::One), Screen::Victory);
+        assert_eq!(state.winner, Some(Player::One));
+        assert_eq!(state.p1_score.matches, 1);
+        assert_eq!(state.p1_score.touches, 0);
+        assert_eq!(state.last_match.len(), 5);
+        assert!(state.timeline.is_empty());
+    }
+
+    #[test]
+    fn time_up_starts_the_next_period() {
+        let mut state = fenced_to(15, 3);
+        state.clock = 0;
+
+        assert_eq!(state.time_up(Player::One), Screen::NewBout);
+        assert_eq!(state.period, 2);
+        assert_eq!(state.clock, state.rules.period_ticks());
+        assert_eq!(state.priority, None);
+    }
+
+    #[test]
+    fn leader_wins_on_time() {
+        let mut state = fenced_to(15, 1);
+        state.score_touch(Player::Two);
+        state.score_touch(Player::Two);
+        state.score_touch(Player::One);
+
+        assert_eq!(state.time_up(Player::One), Screen::Victory);
+        assert_eq!(state.winner, Some(Player::Two));
+        assert_eq!(state.last_match.len(), 3);
+    }
+
+    #[test]
+    fn tie_on_time_goes_to_the_priority_minute() {
+        let mut state = fenced_to(15, 3);
+        state.period = 3;
+        state.score_touch(Player::One);
+        state.score_touch(Player::Two);
+
+        assert_eq!(state.time_up(Player::Two), Screen::NewBout);
+        assert_eq!(state.priority, Some(Player::Two));
+        assert_eq!(state.clock, seconds_to_ticks(PRIORITY_MINUTE));
+        assert_eq!(state.winner, None);
+    }
+
+    #[test]
+    fn first_touch_of_the_priority_minute_wins() {
+        let mut state = fenced_to(15, 1);
+        state.time_up(Player::Two);
+
+        // even for the fencer without priority.
+        assert_eq!(state.score_touch(Player::One), Screen::Victory);
+        assert_eq!(state.winner, Some(Player::One));
+        assert_eq!(state.priority, None);
+        assert_eq!(state.period, 1);
+    }
+
+    #[test]
+    fn priority_holder_wins_when_the_minute_runs_out() {
+        let mut state = fenced_to(15, 1);
+        state.time_up(Player::Two);
+
+        // the coin is only tossed once.
+        assert_eq!(state.time_up(Player::One), Screen::Victory);
+        assert_eq!(state.winner, Some(Player::Two));
+        assert_eq!(state.p2_score.matches, 1);
+        assert_eq!(state.priority, None);
+        assert_eq!(state.clock, state.rules.period_ticks());
     }
 }
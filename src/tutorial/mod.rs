@@ -0,0 +1,366 @@
+use crate::{
+    bout::{Bout, BoutEvent, FighterInput},
+    combat::{step_bout, BoutInputs},
+    controls::{
+        bindings::{button_label, key_label, Bindings, InputAction},
+        input::{ActionInput, MenuInput},
+    },
+    despawn_buttons, distance,
+    fighter::{Gaurd, Move, Player},
+    resume_clock,
+    state::Screen,
+    ButtonMarker,
+};
+use bevy::prelude::*;
+
+/// how long (in seconds) the tutorial waits after a lesson is done, before starting the next.
+const LESSON_PAUSE: f32 = 2.0;
+/// how long (in seconds) before the fighters are put back, when the bout ends with the lesson
+/// not done.
+const RETRY_PAUSE: f32 = 1.5;
+/// in the parry lesson the dummy lunges from between these distances (in meters), where its
+/// lunge stops just short. closer than that, it backs off.
+const DUMMY_LUNGE_NEAR: f32 = 1.1;
+const DUMMY_LUNGE_FAR: f32 = 1.25;
+/// how close (in meters) the dummy comes in the beat lesson.
+const DUMMY_BEAT_DISTANCE: f32 = 1.5;
+/// where the dummy starts the lessons it comes forward in, closer than its en garde line so the
+/// player is not kept waiting.
+const DUMMY_START: f32 = 0.5;
+/// how fast the game clock runs in the parry lesson, slowed so there is time to see the lunge
+/// coming.
+const PARRY_SPEED: f32 = 0.5;
+
+/// the steps of the tutorial, in the order they are taught.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lesson {
+    Advance,
+    Lunge,
+    Gaurd,
+    Parry,
+    Beat,
+    SideFlip,
+    /// every lesson has been done.
+    Done,
+}
+
+impl Lesson {
+    fn next(self) -> Self {
+        match self {
+            Self::Advance => Self::Lunge,
+            Self::Lunge => Self::Gaurd,
+            Self::Gaurd => Self::Parry,
+            Self::Parry => Self::Beat,
+            Self::Beat => Self::SideFlip,
+            Self::SideFlip | Self::Done => Self::Done,
+        }
+    }
+
+    /// which lesson this is, counting from 1.
+    fn number(self) -> usize {
+        self as usize + 1
+    }
+
+    /// the line the dummy holds its blade in.
+    fn dummy_line(self) -> Gaurd {
+        match self {
+            Self::Gaurd => Gaurd::Up,
+            Self::Parry | Self::Beat => Gaurd::Right,
+            _ => Gaurd::Left,
+        }
+    }
+
+    /// what the player is asked to do.
+    fn text(self, bindings: &Bindings) -> String {
+        match self {
+            Self::Advance => format!(
+                "advance ({}) toward the dummy. whoever advances first takes right of way, and \
+                 only they can score with an attack.",
+                control(bindings, InputAction::Advance)
+            ),
+            Self::Lunge => format!(
+                "take right of way by advancing, then lunge ({}) from about a meter away to \
+                 score a touch.",
+                control(bindings, InputAction::Lunge)
+            ),
+            Self::Gaurd => format!(
+                "your gaurd is the line your blade covers, the icons show each fencers. switch \
+                 yours to match the dummys, up ({}).",
+                control(bindings, InputAction::GaurdUp)
+            ),
+            Self::Parry => format!(
+                "the dummy is about to lunge at you in the right line, in slow motion. as the \
+                 lunge comes in, switch into that gaurd ({}) to parry it and steal right of way.",
+                control(bindings, InputAction::GaurdRight)
+            ),
+            Self::Beat => format!(
+                "the dummy has right of way. get within a meter, match its gaurd (right), and \
+                 beat its blade ({}) to steal it.",
+                control(bindings, InputAction::Beat)
+            ),
+            Self::SideFlip => "fencers who pass each other are put back on their lines, with no \
+                 touch. advance right through the dummy."
+                .to_string(),
+            Self::Done => "that's everything! fence the computer from the mode select screen to \
+                 put it to use. press enter (or A) to go back."
+                .to_string(),
+        }
+    }
+}
+
+/// the key and gamepad button bound to `action`.
+fn control(bindings: &Bindings, action: InputAction) -> String {
+    format!(
+        "{} or {}",
+        key_label(bindings.key(action)),
+        button_label(bindings.button(action))
+    )
+}
+
+/// how far through the tutorial the player is.
+#[derive(Resource, Debug)]
+struct Tutorial {
+    lesson: Lesson,
+    /// wether the lesson has been done, the next one starts once the wait is over.
+    done: bool,
+    /// what just happened, shown under the lesson.
+    note: &'static str,
+    /// counts down to the next lesson, or to putting the fighters back.
+    wait: Option<Timer>,
+}
+
+#[derive(Component)]
+struct TutorialText;
+
+/// teaches a new player the basics, one step at a time, against a scripted dummy
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::HowToPlay), (start_tutorial, spawn_overlay))
+            .add_systems(
+                OnExit(Screen::HowToPlay),
+                (despawn_buttons, end_tutorial, resume_clock),
+            )
+            .add_systems(
+                FixedUpdate,
+                dummy.before(step_bout).run_if(in_state(Screen::HowToPlay)),
+            )
+            .add_systems(
+                FixedUpdate,
+                follow_lesson
+                    .after(step_bout)
+                    .run_if(in_state(Screen::HowToPlay)),
+            )
+            .add_systems(
+                Update,
+                (next_lesson, leave_tutorial, show_lesson).run_if(in_state(Screen::HowToPlay)),
+            );
+    }
+}
+
+fn start_tutorial(mut commands: Commands, mut bout: ResMut<Bout>, mut time: ResMut<Time<Virtual>>) {
+    // player one is fenced from this machine, the dummy is the computers.
+    *bout = Bout::new();
+    start_lesson(&mut bout, &mut time, Lesson::Advance);
+
+    commands.insert_resource(Tutorial {
+        lesson: Lesson::Advance,
+        done: false,
+        note: "",
+        wait: None,
+    });
+}
+
+fn end_tutorial(mut commands: Commands) {
+    commands.remove_resource::<Tutorial>();
+}
+
+/// puts the fighters back on their lines, set up for `lesson`.
+fn start_lesson(bout: &mut Bout, time: &mut Time<Virtual>, lesson: Lesson) {
+    bout.reset();
+    time.set_relative_speed(if lesson == Lesson::Parry {
+        PARRY_SPEED
+    } else {
+        1.0
+    });
+    bout.p2.gaurd = lesson.dummy_line();
+
+    if matches!(lesson, Lesson::Parry | Lesson::Beat) {
+        bout.p2.position = DUMMY_START;
+    }
+
+    if lesson == Lesson::Beat {
+        bout.state.row = Some(Player::Two);
+    }
+}
+
+/// drives the dummy. it only does what the lesson needs, and nothing once it is done.
+fn dummy(bout: Res<Bout>, tutorial: Res<Tutorial>, mut inputs: ResMut<BoutInputs>) {
+    inputs.p2 = if tutorial.done {
+        FighterInput::default()
+    } else {
+        dummy_input(tutorial.lesson, &bout)
+    };
+}
+
+fn dummy_input(lesson: Lesson, bout: &Bout) -> FighterInput {
+    let distance = distance(bout.p1.position, bout.p2.position);
+
+    let action = match lesson {
+        _ if !bout.can_act(Player::Two) => None,
+        Lesson::Parry if distance > DUMMY_LUNGE_FAR => Some(Move::Advance),
+        Lesson::Parry if distance < DUMMY_LUNGE_NEAR => Some(Move::Retreat),
+        Lesson::Parry => Some(Move::Lunge),
+        Lesson::Beat if distance > DUMMY_BEAT_DISTANCE => Some(Move::Advance),
+        _ => None,
+    };
+
+    FighterInput {
+        action,
+        gaurd: Some(lesson.dummy_line()),
+        ..default()
+    }
+}
+
+/// checks if the player has done what the lesson asks, or if the bout ended without it.
+fn follow_lesson(
+    bout: Res<Bout>,
+    mut tutorial: ResMut<Tutorial>,
+    mut bout_events: EventReader<BoutEvent>,
+) {
+    let events: Vec<BoutEvent> = bout_events.read().copied().collect();
+
+    if tutorial.wait.is_some() {
+        return;
+    }
+
+    let happened = |event: BoutEvent| events.contains(&event);
+
+    let done = match tutorial.lesson {
+        Lesson::Advance => bout.state.row == Some(Player::One),
+        Lesson::Lunge => events
+            .iter()
+            .any(|event| matches!(event, BoutEvent::Touch(Player::One, _))),
+        Lesson::Gaurd => bout.p1.parries(&bout.p2),
+        Lesson::Parry => happened(BoutEvent::Parry(Player::One)),
+        Lesson::Beat => happened(BoutEvent::Beat(Player::One)),
+        Lesson::SideFlip => happened(BoutEvent::SideFlip),
+        Lesson::Done => false,
+    };
+
+    if done {
+        tutorial.done = true;
+        tutorial.note = "well done!";
+        tutorial.wait = Some(Timer::from_seconds(LESSON_PAUSE, TimerMode::Once));
+    } else if bout.over && tutorial.lesson != Lesson::Done {
+        tutorial.note = if events
+            .iter()
+            .any(|event| matches!(event, BoutEvent::Touch(Player::Two, _)))
+        {
+            "the dummy touched you, try again."
+        } else if happened(BoutEvent::SideFlip) {
+            "you passed the dummy, try again."
+        } else {
+            "try again."
+        };
+        tutorial.wait = Some(Timer::from_seconds(RETRY_PAUSE, TimerMode::Once));
+    }
+}
+
+/// once the wait is over, starts the next lesson, or the same one again. the wait is timed in
+/// real time, so it is not stretched by a slowed lesson.
+fn next_lesson(
+    real_time: Res<Time<Real>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut tutorial: ResMut<Tutorial>,
+    mut bout: ResMut<Bout>,
+) {
+    let Some(wait) = tutorial.wait.as_mut() else {
+        return;
+    };
+
+    wait.tick(real_time.delta());
+
+    if !wait.finished() {
+        return;
+    }
+
+    if tutorial.done {
+        tutorial.lesson = tutorial.lesson.next();
+        tutorial.done = false;
+        tutorial.note = "";
+    }
+
+    tutorial.wait = None;
+    start_lesson(&mut bout, &mut time, tutorial.lesson);
+}
+
+/// the pause key (or button) leaves the tutorial, as does pressing enter once it is done.
+fn leave_tutorial(
+    action_input: ActionInput,
+    menu_input: Res<MenuInput>,
+    tutorial: Res<Tutorial>,
+    mut next_state: ResMut<NextState<Screen>>,
+) {
+    if action_input.pressed(0, InputAction::Pause)
+        || (tutorial.lesson == Lesson::Done && menu_input.press)
+    {
+        next_state.set(Screen::Welcome);
+    }
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            ButtonMarker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 12.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                TutorialText,
+            ));
+        });
+}
+
+fn show_lesson(
+    tutorial: Res<Tutorial>,
+    bindings: Res<Bindings>,
+    mut text_query: Query<&mut Text, With<TutorialText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let heading = match tutorial.lesson {
+        Lesson::Done => "how to play".to_string(),
+        lesson => format!(
+            "how to play, lesson {}/{}",
+            lesson.number(),
+            Lesson::Done.number() - 1
+        ),
+    };
+
+    text.sections[0].value = format!(
+        "{heading}\n\n{}\n\n{}\n\n{}: leave the tutorial",
+        tutorial.lesson.text(&bindings),
+        tutorial.note,
+        control(&bindings, InputAction::Pause),
+    );
+}
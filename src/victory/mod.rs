@@ -0,0 +1,342 @@
+use crate::{
+    bout::Bout,
+    controls::input::MenuInput,
+    despawn_buttons,
+    fighter::Player,
+    state::{GameState, Screen},
+    ButtonInteractions, ButtonMarker,
+};
+use bevy::prelude::*;
+
+#[derive(Component, Debug, Copy, Clone, PartialEq, Eq)]
+enum VictoryMenuButton {
+    Rematch,
+    ChangeMode,
+    Quit,
+}
+
+impl VictoryMenuButton {
+    fn next(index: VictoryMenuButton) -> Self {
+        match index {
+            Self::Rematch => Self::ChangeMode,
+            Self::ChangeMode => Self::Quit,
+            Self::Quit => Self::Rematch,
+        }
+    }
+
+    fn prev(index: VictoryMenuButton) -> Self {
+        match index {
+            Self::Rematch => Self::Quit,
+            Self::ChangeMode => Self::Rematch,
+            Self::Quit => Self::ChangeMode,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SelectedButton(Option<VictoryMenuButton>, Interaction);
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+
+/// used to announce which player won the match
+pub struct VictoryScreenPlugin;
+
+impl Plugin for VictoryScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::Victory), spawn_buttons)
+            .add_systems(OnExit(Screen::Victory), despawn_buttons)
+            .add_systems(Update, keyboard_select.run_if(in_state(Screen::Victory)))
+            .add_systems(Update, mouse_select.run_if(in_state(Screen::Victory)))
+            .add_systems(Update, button_selection.run_if(in_state(Screen::Victory)));
+    }
+}
+
+/// spawns the buttons (and button text) of the victory menu
+fn spawn_buttons(mut commands: Commands, bout: Res<Bout>) {
+    debug!("spawning victory menu buttons");
+
+    let (p1_touches, p2_touches) = final_touches(&bout.state);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ButtonMarker,
+        ))
+        // spawn winner annoucement
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                match bout.state.winner {
+                    Some(winner) => format!("Player {winner:?} wins the match!"),
+                    None => "The match is over!".to_string(),
+                },
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+        })
+        // spawn final score
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Player One => {p1_touches:<2} | Player Two => {p2_touches:<2}"),
+                TextStyle {
+                    font_size: 24.0,
+                    ..default()
+                },
+            ));
+        })
+        // spawn matches won
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "Matches won: Player One => {} | Player Two => {}",
+                    bout.state.p1_score.matches, bout.state.p2_score.matches
+                ),
+                TextStyle {
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+        })
+        // spawn the timeline of touches
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    timeline(&bout.state),
+                    TextStyle {
+                        font_size: 12.0,
+                        color: Color::rgb(0.75, 0.75, 0.75),
+                        ..default()
+                    },
+                )
+                .with_text_justify(JustifyText::Center)
+                .with_style(Style {
+                    max_width: Val::Percent(80.0),
+                    ..default()
+                }),
+            );
+        })
+        // Rematch button
+        .with_children(|parent| {
+            spawn_button(parent, "Rematch", VictoryMenuButton::Rematch);
+        })
+        // Change Mode button
+        .with_children(|parent| {
+            spawn_button(parent, "Change Mode", VictoryMenuButton::ChangeMode);
+        })
+        // Quit button
+        .with_children(|parent| {
+            spawn_button(parent, "Quit", VictoryMenuButton::Quit);
+        });
+
+    commands.insert_resource(SelectedButton(None, Interaction::None));
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, button: VictoryMenuButton) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                width: Val::Vw(10.0),
+                height: Val::Vh(5.0),
+                border: UiRect::all(Val::Px(1.0)),
+                // horizontally center child text
+                justify_content: JustifyContent::Center,
+                // vertically center child text
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            border_color: BorderColor(Color::BLACK),
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ),
+                button,
+            ));
+        });
+}
+
+/// the touches each player had when the match was won. the score itself is reset as soon as
+/// the match is, so they are counted from the timeline.
+fn final_touches(state: &GameState) -> (usize, usize) {
+    let count = |player: Player| {
+        state
+            .last_match
+            .iter()
+            .filter(|touch| touch.player == player)
+            .count()
+    };
+
+    (count(Player::One), count(Player::Two))
+}
+
+/// who scored when, with the score after each touch. touches scored against the clock say
+/// how much time was left.
+fn timeline(state: &GameState) -> String {
+    let (mut p1, mut p2) = (0, 0);
+
+    state
+        .last_match
+        .iter()
+        .map(|touch| {
+            match touch.player {
+                Player::One => p1 += 1,
+                Player::Two => p2 += 1,
+            }
+
+            let mut entry = format!(
+                "{p1}-{p2} Player {:?}, {}",
+                touch.player,
+                touch.phrase.label()
+            );
+
+            if state.rules.period.is_some() {
+                entry.push_str(&format!(
+                    " ({}:{:02} left in period {})",
+                    touch.seconds / 60,
+                    touch.seconds % 60,
+                    touch.period
+                ));
+            }
+
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join("  |  ")
+}
+
+/// handles using the keyboard to select a button
+fn keyboard_select(menu_input: Res<MenuInput>, mut button_selection: ResMut<SelectedButton>) {
+    match button_selection.0 {
+        Some(button) => {
+            if menu_input.next {
+                debug!("changing button selection to next button with keeb");
+                button_selection.0 = Some(VictoryMenuButton::next(button));
+            } else if menu_input.prev {
+                debug!("changing button selection to previous button with keeb");
+                button_selection.0 = Some(VictoryMenuButton::prev(button));
+            } else if menu_input.press {
+                debug!("pressing button with keeb");
+                button_selection.1 = Interaction::Pressed;
+            }
+        }
+        None => {
+            if menu_input.prev || menu_input.next {
+                debug!("selecting rematch button with keeb");
+                button_selection.0 = Some(VictoryMenuButton::Rematch);
+                button_selection.1 = Interaction::Hovered;
+            }
+        }
+    }
+}
+
+/// handles using the mouse to select a button
+fn mouse_select(
+    mut interaction_query: ButtonInteractions,
+    mut text_query: Query<&VictoryMenuButton, With<Text>>,
+    mut button_selection: ResMut<SelectedButton>,
+) {
+    for (interaction, children) in &mut interaction_query {
+        let Ok(button_type) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        match *interaction {
+            Interaction::Pressed | Interaction::Hovered => {
+                debug!("selecting with mouse");
+                *button_selection = SelectedButton(Some(*button_type), *interaction);
+            }
+            Interaction::None => {
+                debug!("unselecting with mouse");
+                *button_selection = SelectedButton(None, *interaction);
+            }
+        }
+    }
+}
+
+/// handles changing the selected buttons collor and text
+fn button_selection(
+    mut button_query: Query<(&mut BackgroundColor, &mut BorderColor, &Children), With<Button>>,
+    mut text_query: Query<(&mut Text, &VictoryMenuButton)>,
+    mut next_state: ResMut<NextState<Screen>>,
+    button_selection: Res<SelectedButton>,
+) {
+    let select_prefix = "> ";
+
+    for (mut color, mut border_color, children) in &mut button_query {
+        let Ok((mut text, button_type)) = text_query.get_mut(children[0]) else {
+            continue;
+        };
+
+        if Some(*button_type) == button_selection.0 {
+            match button_selection.1 {
+                Interaction::Pressed => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = PRESSED_BUTTON.into();
+                    next_state.set(press_button(*button_type));
+                }
+                Interaction::Hovered => {
+                    if !text.sections[0].value.starts_with(select_prefix) {
+                        text.sections[0].value =
+                            format!("{select_prefix}{}", text.sections[0].value);
+                    }
+
+                    *color = HOVERED_BUTTON.into();
+                    border_color.0 = Color::WHITE;
+                }
+                Interaction::None => {
+                    text.sections[0].value = text.sections[0]
+                        .value
+                        .strip_prefix(select_prefix)
+                        .unwrap_or(text.sections[0].value.as_str())
+                        .to_string();
+                    *color = NORMAL_BUTTON.into();
+                }
+            }
+        } else {
+            text.sections[0].value = text.sections[0]
+                .value
+                .strip_prefix(select_prefix)
+                .unwrap_or(text.sections[0].value.as_str())
+                .to_string();
+            *color = NORMAL_BUTTON.into();
+            border_color.0 = Color::BLACK;
+        }
+    }
+}
+
+/// called by mouse select or keyboard select. used to envoke the buttons function
+fn press_button(button: VictoryMenuButton) -> Screen {
+    match button {
+        VictoryMenuButton::Rematch => {
+            info!("starting the next match");
+            Screen::NewBout
+        }
+        VictoryMenuButton::ChangeMode => Screen::ModeSelect,
+        VictoryMenuButton::Quit => Screen::Welcome,
+    }
+}
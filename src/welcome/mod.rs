@@ -1,14 +1,13 @@
 use crate::{
-    despawn_buttons,
-    setup::cleanup_after_bout,
-    state::{GameState, Screen},
-    ButtonMarker,
+    bout::Bout, controls::input::MenuInput, despawn_buttons, setup::cleanup_after_bout,
+    state::Screen, ButtonInteractions, ButtonMarker,
 };
 use bevy::prelude::*;
 
 #[derive(Component, Clone, Copy, PartialEq, Eq)]
 enum WelcomeMenuButton {
     Play,
+    Replays,
     Controls,
     HowToPlay,
     Exit,
@@ -17,7 +16,8 @@ enum WelcomeMenuButton {
 impl WelcomeMenuButton {
     fn next(index: WelcomeMenuButton) -> Self {
         match index {
-            Self::Play => Self::Controls,
+            Self::Play => Self::Replays,
+            Self::Replays => Self::Controls,
             Self::Controls => Self::HowToPlay,
             Self::HowToPlay => Self::Exit,
             Self::Exit => Self::Play,
@@ -27,7 +27,8 @@ impl WelcomeMenuButton {
     fn prev(index: WelcomeMenuButton) -> Self {
         match index {
             Self::Play => Self::Exit,
-            Self::Controls => Self::Play,
+            Self::Replays => Self::Play,
+            Self::Controls => Self::Replays,
             Self::HowToPlay => Self::Controls,
             Self::Exit => Self::HowToPlay,
         }
@@ -119,6 +120,39 @@ fn spawn_buttons(mut commands: Commands) {
                     ));
                 });
         })
+        // Replays button
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Vw(10.0),
+                        height: Val::Vh(5.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: BorderColor(Color::BLACK),
+                    background_color: NORMAL_BUTTON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "Replays",
+                            TextStyle {
+                                // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 16.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        WelcomeMenuButton::Replays,
+                    ));
+                });
+        })
         // Controls button
         .with_children(|parent| {
             parent
@@ -223,30 +257,22 @@ fn spawn_buttons(mut commands: Commands) {
 }
 
 /// handles using the keyboard to select a button
-fn keyboard_select(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut button_selection: ResMut<SelectedButton>,
-) {
+fn keyboard_select(menu_input: Res<MenuInput>, mut button_selection: ResMut<SelectedButton>) {
     match button_selection.0 {
         Some(button) => {
-            if keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.next {
                 debug!("changing button selection to next button with keeb");
                 button_selection.0 = Some(WelcomeMenuButton::next(button));
-            } else if keyboard_input.just_released(KeyCode::ArrowUp) {
+            } else if menu_input.prev {
                 debug!("changing button selection to previous button with keeb");
                 button_selection.0 = Some(WelcomeMenuButton::prev(button));
-            } else if keyboard_input.just_released(KeyCode::Enter) {
+            } else if menu_input.press {
                 debug!("pressing button with keeb");
                 button_selection.1 = Interaction::Pressed;
             }
         }
         None => {
-            if keyboard_input.just_released(KeyCode::ArrowUp)
-                || keyboard_input.just_released(KeyCode::ArrowDown)
-                || keyboard_input.just_released(KeyCode::Tab)
-            {
+            if menu_input.prev || menu_input.next {
                 debug!("selecting continue button with keeb");
                 button_selection.0 = Some(WelcomeMenuButton::Play);
                 button_selection.1 = Interaction::Hovered;
@@ -257,7 +283,7 @@ fn keyboard_select(
 
 /// handles using the mouse to select a button
 fn mouse_select(
-    mut interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: ButtonInteractions,
     mut text_query: Query<&WelcomeMenuButton, With<Text>>,
     mut button_selection: ResMut<SelectedButton>,
 ) {
@@ -335,19 +361,14 @@ fn button_selection(
 fn press_button(button: WelcomeMenuButton) -> Screen {
     match button {
         WelcomeMenuButton::Play => Screen::ModeSelect,
-        WelcomeMenuButton::Controls => {
-            error!("controls menu has yet to programmed");
-            Screen::Welcome
-        }
-        WelcomeMenuButton::HowToPlay => {
-            error!("how to play menu has yet to programmed");
-            Screen::Welcome
-        }
+        WelcomeMenuButton::Replays => Screen::Replay,
+        WelcomeMenuButton::Controls => Screen::Controls,
+        WelcomeMenuButton::HowToPlay => Screen::HowToPlay,
         WelcomeMenuButton::Exit => Screen::ExitGame,
     }
 }
 
-fn cleanup_world_state(mut world_state: ResMut<GameState>) {
-    world_state.reset();
-    world_state.reset_scores();
+fn cleanup_world_state(mut bout: ResMut<Bout>) {
+    bout.reset();
+    bout.state.reset_scores();
 }